@@ -0,0 +1,4 @@
+-- REVERTS USER DISABLING --
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS disabled_at;
@@ -0,0 +1,4 @@
+-- MIGRATION FOR DISABLING USERS --
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMPTZ;
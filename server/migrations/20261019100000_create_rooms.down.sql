@@ -0,0 +1,13 @@
+-- REVERTS ROOMS --
+
+-- messages stay, but lose their room and edit history
+DROP TABLE IF EXISTS message_revisions;
+
+DROP INDEX IF EXISTS messages_room_id_created_at_idx;
+
+ALTER TABLE messages
+    DROP COLUMN IF EXISTS edited_at,
+    DROP COLUMN IF EXISTS room_id;
+
+DROP TABLE IF EXISTS room_members;
+DROP TABLE IF EXISTS rooms;
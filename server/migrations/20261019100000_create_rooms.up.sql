@@ -0,0 +1,52 @@
+-- MIGRATION FOR ROOMS AND MESSAGE EDITING --
+
+CREATE TABLE IF NOT EXISTS rooms (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name TEXT NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS room_members (
+    room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL DEFAULT 'member' CHECK (role IN ('owner', 'admin', 'member')),
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT room_member_pk PRIMARY KEY (room_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS room_members_user_id_idx ON room_members (user_id);
+
+ALTER TABLE messages
+    ADD COLUMN IF NOT EXISTS room_id UUID REFERENCES rooms(id) ON DELETE CASCADE,
+    ADD COLUMN IF NOT EXISTS edited_at TIMESTAMPTZ;
+
+-- messages written before rooms existed are moved into a shared room
+WITH general AS (
+    INSERT INTO rooms (name)
+    SELECT 'general'
+    WHERE EXISTS (SELECT 1 FROM messages WHERE room_id IS NULL)
+    RETURNING id
+)
+UPDATE messages
+    SET room_id = (SELECT id FROM general)
+    WHERE room_id IS NULL;
+
+INSERT INTO room_members (room_id, user_id)
+SELECT DISTINCT room_id, user_id FROM messages
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE messages
+    ALTER COLUMN room_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS messages_room_id_created_at_idx ON messages (room_id, created_at, id);
+
+CREATE TABLE IF NOT EXISTS message_revisions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    edited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS message_revisions_message_id_idx ON message_revisions (message_id, created_at);
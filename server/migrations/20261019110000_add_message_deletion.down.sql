@@ -0,0 +1,8 @@
+-- REVERTS MESSAGE DELETION --
+
+DROP INDEX IF EXISTS messages_pending_purge_idx;
+
+ALTER TABLE messages
+    DROP COLUMN IF EXISTS purged_at,
+    DROP COLUMN IF EXISTS deleted_by,
+    DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,9 @@
+-- MIGRATION FOR SOFT DELETING MESSAGES --
+
+ALTER TABLE messages
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS purged_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS messages_pending_purge_idx ON messages (deleted_at)
+    WHERE deleted_at IS NOT NULL AND purged_at IS NULL;
@@ -0,0 +1,8 @@
+-- REVERTS MESSAGE THREADS --
+
+DROP INDEX IF EXISTS messages_parent_id_created_at_idx;
+
+ALTER TABLE messages
+    DROP COLUMN IF EXISTS last_reply_at,
+    DROP COLUMN IF EXISTS reply_count,
+    DROP COLUMN IF EXISTS parent_id;
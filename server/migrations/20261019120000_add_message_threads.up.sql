@@ -0,0 +1,9 @@
+-- MIGRATION FOR THREADED REPLIES --
+
+ALTER TABLE messages
+    ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES messages(id) ON DELETE CASCADE,
+    ADD COLUMN IF NOT EXISTS reply_count INT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS last_reply_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS messages_parent_id_created_at_idx ON messages (parent_id, created_at, id)
+    WHERE parent_id IS NOT NULL;
@@ -0,0 +1,4 @@
+-- REVERTS MESSAGE REACTIONS --
+
+DROP TABLE IF EXISTS message_reactions;
+DROP TABLE IF EXISTS custom_emojis;
@@ -0,0 +1,15 @@
+-- MIGRATION FOR MESSAGE REACTIONS --
+
+-- shortcodes that may be used as reactions besides unicode emoji, e.g. ':party_parrot:'
+CREATE TABLE IF NOT EXISTS custom_emojis (
+    shortcode TEXT PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS message_reactions (
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    emoji TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT message_reaction_pk PRIMARY KEY (message_id, user_id, emoji)
+);
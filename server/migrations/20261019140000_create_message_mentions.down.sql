@@ -0,0 +1,3 @@
+-- REVERTS MESSAGE MENTIONS --
+
+DROP TABLE IF EXISTS message_mentions;
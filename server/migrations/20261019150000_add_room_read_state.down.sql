@@ -0,0 +1,5 @@
+-- REVERTS ROOM READ STATE --
+
+ALTER TABLE room_members
+    DROP COLUMN IF EXISTS last_read_at,
+    DROP COLUMN IF EXISTS last_read_message_id;
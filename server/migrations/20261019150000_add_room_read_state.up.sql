@@ -0,0 +1,5 @@
+-- MIGRATION FOR READ RECEIPTS --
+
+ALTER TABLE room_members
+    ADD COLUMN IF NOT EXISTS last_read_message_id UUID REFERENCES messages(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS last_read_at TIMESTAMPTZ;
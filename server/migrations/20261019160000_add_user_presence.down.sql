@@ -0,0 +1,5 @@
+-- REVERTS USER PRESENCE --
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS presence_override,
+    DROP COLUMN IF EXISTS last_seen_at;
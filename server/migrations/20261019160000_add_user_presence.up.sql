@@ -0,0 +1,6 @@
+-- MIGRATION FOR USER PRESENCE --
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ,
+    -- manual status that replaces the one derived from sessions while the user is connected
+    ADD COLUMN IF NOT EXISTS presence_override TEXT CHECK (presence_override IN ('away', 'dnd'));
@@ -0,0 +1,3 @@
+-- REVERTS MESSAGE PINS --
+
+DROP TABLE IF EXISTS message_pins;
@@ -0,0 +1,10 @@
+-- MIGRATION FOR PINNED MESSAGES --
+
+CREATE TABLE IF NOT EXISTS message_pins (
+    message_id UUID PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    pinned_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    pinned_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS message_pins_room_id_idx ON message_pins (room_id, pinned_at);
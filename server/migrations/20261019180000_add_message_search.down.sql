@@ -0,0 +1,6 @@
+-- REVERTS FULL-TEXT MESSAGE SEARCH --
+
+DROP INDEX IF EXISTS messages_content_tsv_idx;
+
+ALTER TABLE messages
+    DROP COLUMN IF EXISTS content_tsv;
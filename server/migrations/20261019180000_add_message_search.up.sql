@@ -0,0 +1,7 @@
+-- MIGRATION FOR FULL-TEXT MESSAGE SEARCH --
+
+ALTER TABLE messages
+    ADD COLUMN IF NOT EXISTS content_tsv TSVECTOR
+        GENERATED ALWAYS AS (to_tsvector('english', content)) STORED;
+
+CREATE INDEX IF NOT EXISTS messages_content_tsv_idx ON messages USING GIN (content_tsv);
@@ -0,0 +1,4 @@
+-- REVERTS MESSAGE ATTACHMENTS --
+
+-- the stored objects are left behind
+DROP TABLE IF EXISTS attachments;
@@ -0,0 +1,13 @@
+-- REVERTS IMAGE THUMBNAILS --
+
+DROP TABLE IF EXISTS attachment_thumbnails;
+
+DROP INDEX IF EXISTS attachments_unprocessed_idx;
+
+ALTER TABLE attachments ALTER COLUMN created_at SET DEFAULT now();
+
+ALTER TABLE attachments
+    DROP COLUMN IF EXISTS processed_at,
+    DROP COLUMN IF EXISTS blurhash,
+    DROP COLUMN IF EXISTS height,
+    DROP COLUMN IF EXISTS width;
@@ -0,0 +1,26 @@
+-- MIGRATION FOR IMAGE THUMBNAILS --
+
+-- filled in by the image worker; processed_at is set even when the image could not be decoded
+ALTER TABLE attachments
+    ADD COLUMN IF NOT EXISTS width INT,
+    ADD COLUMN IF NOT EXISTS height INT,
+    ADD COLUMN IF NOT EXISTS blurhash TEXT,
+    ADD COLUMN IF NOT EXISTS processed_at TIMESTAMPTZ;
+
+-- files uploaded together share a transaction, clock_timestamp() keeps their order
+ALTER TABLE attachments ALTER COLUMN created_at SET DEFAULT clock_timestamp();
+
+-- the worker's queue; images uploaded before this migration are picked up too
+CREATE INDEX IF NOT EXISTS attachments_unprocessed_idx ON attachments (created_at)
+    WHERE processed_at IS NULL AND content_type LIKE 'image/%';
+
+CREATE TABLE IF NOT EXISTS attachment_thumbnails (
+    attachment_id UUID NOT NULL REFERENCES attachments(id) ON DELETE CASCADE,
+    size TEXT NOT NULL,
+    width INT NOT NULL,
+    height INT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    storage_key TEXT NOT NULL UNIQUE,
+    PRIMARY KEY (attachment_id, size)
+);
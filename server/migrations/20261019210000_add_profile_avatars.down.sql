@@ -0,0 +1,6 @@
+-- REVERTS PROFILE AVATARS --
+
+ALTER TABLE profiles
+    DROP COLUMN IF EXISTS avatar_updated_at,
+    DROP COLUMN IF EXISTS avatar_content_type,
+    DROP COLUMN IF EXISTS avatar_key;
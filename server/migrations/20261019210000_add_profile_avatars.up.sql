@@ -0,0 +1,7 @@
+-- MIGRATION FOR PROFILE AVATARS --
+
+-- every size is stored under `{avatar_key}.{size}`, profiles without one get an identicon
+ALTER TABLE profiles
+    ADD COLUMN IF NOT EXISTS avatar_key TEXT UNIQUE,
+    ADD COLUMN IF NOT EXISTS avatar_content_type TEXT,
+    ADD COLUMN IF NOT EXISTS avatar_updated_at TIMESTAMPTZ;
@@ -0,0 +1,4 @@
+-- REVERTS LINK PREVIEWS --
+
+DROP TABLE IF EXISTS message_links;
+DROP TABLE IF EXISTS link_previews;
@@ -0,0 +1,27 @@
+-- MIGRATION FOR LINK PREVIEWS --
+
+-- one row per URL, shared by every message linking it; fetched_at is NULL while
+-- the fetch is pending and set even when the page had nothing to show
+CREATE TABLE IF NOT EXISTS link_previews (
+    url TEXT PRIMARY KEY,
+    title TEXT,
+    description TEXT,
+    image_url TEXT,
+    site_name TEXT,
+    fetched_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- the worker's queue
+CREATE INDEX IF NOT EXISTS link_previews_pending_idx ON link_previews (created_at)
+    WHERE fetched_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS message_links (
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    url TEXT NOT NULL REFERENCES link_previews(url) ON DELETE CASCADE,
+    -- order of the links in the message
+    position INT NOT NULL,
+    CONSTRAINT message_link_pk PRIMARY KEY (message_id, url)
+);
+
+CREATE INDEX IF NOT EXISTS message_links_url_idx ON message_links (url);
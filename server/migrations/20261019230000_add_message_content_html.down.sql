@@ -0,0 +1,4 @@
+-- REVERTS RICH TEXT MESSAGES --
+
+ALTER TABLE messages
+    DROP COLUMN IF EXISTS content_html;
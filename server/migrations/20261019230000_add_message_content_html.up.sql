@@ -0,0 +1,12 @@
+-- MIGRATION FOR RICH TEXT MESSAGES --
+
+-- sanitised HTML rendered from content whenever a message is sent or edited
+ALTER TABLE messages
+    ADD COLUMN IF NOT EXISTS content_html TEXT NOT NULL DEFAULT '';
+
+-- earlier messages were written as plain text, so they are only escaped
+UPDATE messages
+    SET content_html = '<p>' || replace(replace(replace(replace(replace(
+            btrim(content, E'\r\n'), E'\r', ''), '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+            E'\n', '<br>') || '</p>'
+    WHERE content_html = '' AND btrim(content) <> '';
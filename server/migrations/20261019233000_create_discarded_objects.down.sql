@@ -0,0 +1,4 @@
+-- REVERTS DISCARDED STORAGE OBJECTS --
+
+-- objects still queued are left behind in storage
+DROP TABLE IF EXISTS discarded_objects;
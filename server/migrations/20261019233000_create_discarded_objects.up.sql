@@ -0,0 +1,26 @@
+-- MIGRATION FOR DISCARDED STORAGE OBJECTS --
+
+-- objects whose rows are gone, deleted from storage by the purge job
+CREATE TABLE IF NOT EXISTS discarded_objects (
+    storage_key TEXT PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- messages purged before this migration still kept their mentions, links and attachments
+INSERT INTO discarded_objects (storage_key)
+    SELECT a.storage_key FROM attachments a
+        JOIN messages m ON m.id = a.message_id
+        WHERE m.purged_at IS NOT NULL
+    UNION ALL
+    SELECT t.storage_key FROM attachment_thumbnails t
+        JOIN attachments a ON a.id = t.attachment_id
+        JOIN messages m ON m.id = a.message_id
+        WHERE m.purged_at IS NOT NULL
+    ON CONFLICT DO NOTHING;
+
+DELETE FROM attachments
+    WHERE message_id IN (SELECT id FROM messages WHERE purged_at IS NOT NULL);
+DELETE FROM message_mentions
+    WHERE message_id IN (SELECT id FROM messages WHERE purged_at IS NOT NULL);
+DELETE FROM message_links
+    WHERE message_id IN (SELECT id FROM messages WHERE purged_at IS NOT NULL);
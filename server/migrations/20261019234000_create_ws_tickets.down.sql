@@ -0,0 +1,3 @@
+-- REVERTS WEBSOCKET TICKETS --
+
+DROP TABLE IF EXISTS ws_tickets;
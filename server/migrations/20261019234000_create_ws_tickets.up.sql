@@ -0,0 +1,10 @@
+-- MIGRATION FOR WEBSOCKET TICKETS --
+
+-- single-use tickets for the WebSocket handshake, which cannot carry an Authorization header
+CREATE TABLE IF NOT EXISTS ws_tickets (
+    ticket_hash TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ws_tickets_expires_at_idx ON ws_tickets (expires_at);
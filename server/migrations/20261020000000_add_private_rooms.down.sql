@@ -0,0 +1,6 @@
+-- REVERTS PRIVATE ROOMS --
+
+DROP TABLE IF EXISTS room_invites;
+
+ALTER TABLE rooms
+    DROP COLUMN IF EXISTS is_private;
@@ -0,0 +1,14 @@
+-- MIGRATION FOR PRIVATE ROOMS --
+
+-- existing rooms stay open to everyone
+ALTER TABLE rooms
+    ADD COLUMN IF NOT EXISTS is_private BOOLEAN NOT NULL DEFAULT false;
+
+-- a private room is joined with an invite from one of its owners or admins, used up on join
+CREATE TABLE IF NOT EXISTS room_invites (
+    room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT room_invite_pk PRIMARY KEY (room_id, user_id)
+);
@@ -0,0 +1,329 @@
+use actix_multipart::Multipart;
+use actix_web::{
+    HttpResponse, Responder,
+    http::header::{
+        CacheControl, CacheDirective, Charset, ContentDisposition, DispositionParam,
+        DispositionType, ExtendedValue, X_CONTENT_TYPE_OPTIONS,
+    },
+    mime, web,
+};
+use bytes::{Bytes, BytesMut};
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::attachments::{
+            AttachmentEntity, AttachmentResponse, MAX_FILES_PER_UPLOAD, SignedUrlQuery,
+            SignedUrlResponse, ThumbnailEntity, UploadedFile,
+        },
+        request_error::{RequestError, RequestResult},
+        services::attachment_service,
+    },
+    core::{app_config::UploadLimits, app_data::AppData},
+};
+
+const FILE_FIELD: &str = "file";
+
+#[tracing::instrument(name = "upload_attachments", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/rooms/{id}/attachments", request_body(content_type = "multipart/form-data", description = "one or more `file` parts"), responses((status = 201, description = "files uploaded successfully", body = Vec<AttachmentResponse>)))]
+pub async fn upload_attachments(
+    path: web::Path<Uuid>,
+    payload: Multipart,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let room_id = path.into_inner();
+    let app_data = app_data.into_inner();
+    let files = read_files(payload, &app_data.upload_limits).await?;
+
+    let response = attachment_service::upload_attachments(
+        claims.sub,
+        room_id,
+        files,
+        &app_data.upload_limits,
+        &app_data.pool,
+        app_data.storage.as_ref(),
+        &app_data.image_jobs,
+    )
+    .await;
+
+    match &response {
+        Ok(_) => tracing::info!("The files have been successfully uploaded!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // Vec<AttachmentResponse>
+    Ok(HttpResponse::Created().json(response?))
+}
+
+#[tracing::instrument(name = "download_attachment", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/attachments/{id}", responses((status = 200, description = "attachment contents", content_type = "application/octet-stream")))]
+pub async fn download_attachment(
+    path: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let attachment_id = path.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = attachment_service::download_attachment(
+        claims.sub,
+        attachment_id,
+        &app_data.pool,
+        app_data.storage.as_ref(),
+    )
+    .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    let (attachment, data) = response?;
+
+    // attachment contents
+    Ok(attachment_contents(attachment, data))
+}
+
+#[tracing::instrument(name = "download_thumbnail", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/attachments/{id}/thumbnails/{size}", responses((status = 200, description = "thumbnail of an image attachment", content_type = "image/jpeg")))]
+pub async fn download_thumbnail(
+    path: web::Path<(Uuid, String)>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let (attachment_id, size) = path.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = attachment_service::download_thumbnail(
+        claims.sub,
+        attachment_id,
+        &size,
+        &app_data.pool,
+        app_data.storage.as_ref(),
+    )
+    .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    let (thumbnail, data) = response?;
+
+    // thumbnail contents, always JPEG or PNG
+    Ok(thumbnail_contents(thumbnail, data))
+}
+
+#[tracing::instrument(name = "sign_attachment_url", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/attachments/{id}/signed-url", responses((status = 200, description = "short-lived URL of the attachment that needs no bearer token", body = SignedUrlResponse)))]
+pub async fn sign_attachment_url(
+    path: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let attachment_id = path.into_inner();
+
+    let response = attachment_service::sign_attachment_url(
+        claims.sub,
+        attachment_id,
+        &app_data.jwt_secret,
+        &app_data.pool,
+    )
+    .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // SignedUrlResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "sign_thumbnail_url", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/attachments/{id}/thumbnails/{size}/signed-url", responses((status = 200, description = "short-lived URL of the thumbnail that needs no bearer token", body = SignedUrlResponse)))]
+pub async fn sign_thumbnail_url(
+    path: web::Path<(Uuid, String)>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let (attachment_id, size) = path.into_inner();
+
+    let response = attachment_service::sign_thumbnail_url(
+        claims.sub,
+        attachment_id,
+        &size,
+        &app_data.jwt_secret,
+        &app_data.pool,
+    )
+    .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // SignedUrlResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "download_signed_attachment", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/files/attachments/{id}", params(SignedUrlQuery), responses((status = 200, description = "attachment contents", content_type = "application/octet-stream")))]
+pub async fn download_signed_attachment(
+    path: web::Path<Uuid>,
+    query: web::Query<SignedUrlQuery>,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let attachment_id = path.into_inner();
+    let app_data = app_data.into_inner();
+
+    let user_id =
+        attachment_service::verify_attachment_url(attachment_id, &query, &app_data.jwt_secret)?;
+    let response = attachment_service::download_attachment(
+        user_id,
+        attachment_id,
+        &app_data.pool,
+        app_data.storage.as_ref(),
+    )
+    .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    let (attachment, data) = response?;
+
+    // attachment contents
+    Ok(attachment_contents(attachment, data))
+}
+
+#[tracing::instrument(name = "download_signed_thumbnail", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/files/attachments/{id}/thumbnails/{size}", params(SignedUrlQuery), responses((status = 200, description = "thumbnail of an image attachment", content_type = "image/jpeg")))]
+pub async fn download_signed_thumbnail(
+    path: web::Path<(Uuid, String)>,
+    query: web::Query<SignedUrlQuery>,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let (attachment_id, size) = path.into_inner();
+    let app_data = app_data.into_inner();
+
+    let user_id = attachment_service::verify_thumbnail_url(
+        attachment_id,
+        &size,
+        &query,
+        &app_data.jwt_secret,
+    )?;
+    let response = attachment_service::download_thumbnail(
+        user_id,
+        attachment_id,
+        &size,
+        &app_data.pool,
+        app_data.storage.as_ref(),
+    )
+    .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    let (thumbnail, data) = response?;
+
+    // thumbnail contents, always JPEG or PNG
+    Ok(thumbnail_contents(thumbnail, data))
+}
+
+fn attachment_contents(attachment: AttachmentEntity, data: Bytes) -> HttpResponse {
+    let content_type = attachment
+        .content_type
+        .parse::<mime::Mime>()
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+    // only images render in the browser, anything else (HTML, SVG, ...) is downloaded
+    // so it cannot run scripts on the API origin
+    let disposition =
+        match content_type.type_() == mime::IMAGE && content_type.subtype() != mime::SVG {
+            true => DispositionType::Inline,
+            false => DispositionType::Attachment,
+        };
+    let filename = match attachment.filename.is_ascii() {
+        true => DispositionParam::Filename(attachment.filename),
+        false => DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".into()),
+            language_tag: None,
+            value: attachment.filename.into_bytes(),
+        }),
+    };
+
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(ContentDisposition {
+            disposition,
+            parameters: vec![filename],
+        })
+        .insert_header((X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .insert_header(CacheControl(vec![CacheDirective::Private]))
+        .body(data)
+}
+
+fn thumbnail_contents(thumbnail: ThumbnailEntity, data: Bytes) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(thumbnail.content_type)
+        .insert_header((X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .insert_header(CacheControl(vec![CacheDirective::Private]))
+        .body(data)
+}
+
+// buffers the `file` parts, giving up as soon as one grows past the size limit;
+// shared with the avatar upload
+pub async fn read_files(
+    mut payload: Multipart,
+    limits: &UploadLimits,
+) -> RequestResult<Vec<UploadedFile>> {
+    let mut files = Vec::new();
+
+    while let Some(field) = payload.next().await {
+        let mut field = field?;
+        if field.name() != Some(FILE_FIELD) {
+            return Err(RequestError::BadRequest(format!(
+                "Unexpected field, files must be sent as `{FILE_FIELD}` parts"
+            )));
+        }
+        if files.len() == MAX_FILES_PER_UPLOAD {
+            return Err(RequestError::BadRequest(format!(
+                "At most {MAX_FILES_PER_UPLOAD} files can be uploaded at once"
+            )));
+        }
+
+        let filename = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .unwrap_or_default()
+            .to_string();
+        let content_type = field.content_type().cloned();
+
+        let mut data = BytesMut::new();
+        while let Some(chunk) = field.next().await {
+            data.extend_from_slice(&chunk?);
+            if data.len() > limits.max_size_bytes {
+                return Err(RequestError::PayloadTooLarge(format!(
+                    "Files may not be larger than {} bytes",
+                    limits.max_size_bytes
+                )));
+            }
+        }
+
+        files.push(UploadedFile {
+            filename,
+            content_type,
+            data: data.freeze(),
+        });
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,34 @@
+use actix_web::{HttpResponse, Responder, web};
+
+use crate::{
+    app::{
+        models::health::{HealthResponse, HealthStatus},
+        services::health_service,
+    },
+    core::app_data::AppData,
+};
+
+#[utoipa::path(get, path = "/health/live", responses((status = 200, description = "process is up", body = HealthResponse)))]
+pub async fn live() -> impl Responder {
+    HttpResponse::Ok().json(health_service::check_liveness())
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "all components are up", body = HealthResponse),
+        (status = 503, description = "at least one component is down", body = HealthResponse),
+    )
+)]
+pub async fn ready(app_data: web::Data<AppData>) -> impl Responder {
+    let response = health_service::check_readiness(&app_data.pool, &app_data.hub).await;
+
+    match response.status {
+        HealthStatus::Up => HttpResponse::Ok().json(response),
+        HealthStatus::Down => {
+            tracing::warn!("Readiness check failed: {:?}", response.components);
+            HttpResponse::ServiceUnavailable().json(response)
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use actix_web::{HttpResponse, Responder, web};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::messages::{
+            CreateMessageRequest, EditMessageRequest, MessageHistoryQuery, MessageResponse,
+            MessageRevisionResponse, PinnedMessageResponse, ThreadResponse,
+        },
+        request_error::RequestResult,
+        services::message_service,
+    },
+    core::app_data::AppData,
+};
+
+#[tracing::instrument(name = "send_message", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/rooms/{id}/messages", request_body = CreateMessageRequest, responses((status = 201, description = "message sent successfully", body = MessageResponse)))]
+pub async fn send_message(
+    message: web::Json<CreateMessageRequest>,
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let message = message.into_inner().try_into()?;
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = message_service::send_message(
+        claims.sub,
+        room_id,
+        message,
+        &app_data.pool,
+        &app_data.hub,
+        &app_data.link_jobs,
+    )
+    .await;
+
+    match &response {
+        Ok(_) => {
+            app_data.metrics.messages_sent.inc();
+            tracing::info!("The message has been successfully sent!");
+        }
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // MessageResponse
+    Ok(HttpResponse::Created().json(response?))
+}
+
+#[tracing::instrument(name = "list_messages", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/rooms/{id}/messages", params(MessageHistoryQuery), responses((status = 200, description = "room history, newest first", body = Vec<MessageResponse>)))]
+pub async fn list_messages(
+    query: web::Query<MessageHistoryQuery>,
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let query = query.into_inner().try_into()?;
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = message_service::list_messages(claims.sub, room_id, query, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<MessageResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "list_mentions", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/me/mentions", params(MessageHistoryQuery), responses((status = 200, description = "messages mentioning the caller, newest first", body = Vec<MessageResponse>)))]
+pub async fn list_mentions(
+    query: web::Query<MessageHistoryQuery>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let query = query.into_inner().try_into()?;
+    let app_data = app_data.into_inner();
+
+    let response = message_service::list_mentions(claims.sub, query, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<MessageResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "get_thread", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/messages/{id}/thread", params(MessageHistoryQuery), responses((status = 200, description = "thread parent with its replies, newest first", body = ThreadResponse)))]
+pub async fn get_thread(
+    query: web::Query<MessageHistoryQuery>,
+    message_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let query = query.into_inner().try_into()?;
+    let message_id = message_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = message_service::get_thread(claims.sub, message_id, query, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // ThreadResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "edit_message", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(patch, path = "/messages/{id}", request_body = EditMessageRequest, responses((status = 200, description = "message edited successfully", body = MessageResponse)))]
+pub async fn edit_message(
+    message: web::Json<EditMessageRequest>,
+    message_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let message = message.into_inner().try_into()?;
+    let message_id = message_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = message_service::edit_message(
+        claims.sub,
+        message_id,
+        message,
+        &app_data.pool,
+        &app_data.hub,
+        &app_data.link_jobs,
+    )
+    .await;
+
+    match &response {
+        Ok(_) => tracing::info!("The message has been successfully edited!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // MessageResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "delete_message", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(delete, path = "/messages/{id}", responses((status = 200, description = "message replaced by a tombstone", body = MessageResponse)))]
+pub async fn delete_message(
+    message_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let message_id = message_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response =
+        message_service::delete_message(claims.sub, message_id, &app_data.pool, &app_data.hub)
+            .await;
+
+    match &response {
+        Ok(_) => tracing::info!("The message has been successfully deleted!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // MessageResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "list_message_revisions", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/messages/{id}/revisions", responses((status = 200, description = "previous versions of the message, oldest first", body = Vec<MessageRevisionResponse>)))]
+pub async fn list_revisions(
+    message_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let message_id = message_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = message_service::list_revisions(claims.sub, message_id, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<MessageRevisionResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "pin_message", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(put, path = "/messages/{id}/pin", responses((status = 201, description = "message pinned successfully", body = PinnedMessageResponse)))]
+pub async fn pin_message(
+    message_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let message_id = message_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response =
+        message_service::pin_message(claims.sub, message_id, &app_data.pool, &app_data.hub).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The message has been successfully pinned!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // PinnedMessageResponse
+    Ok(HttpResponse::Created().json(response?))
+}
+
+#[tracing::instrument(name = "unpin_message", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(delete, path = "/messages/{id}/pin", responses((status = 200, description = "message unpinned successfully", body = PinnedMessageResponse)))]
+pub async fn unpin_message(
+    message_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let message_id = message_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response =
+        message_service::unpin_message(claims.sub, message_id, &app_data.pool, &app_data.hub).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The message has been successfully unpinned!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // PinnedMessageResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "list_pins", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/rooms/{id}/pins", responses((status = 200, description = "pinned messages of the room, oldest pin first", body = Vec<PinnedMessageResponse>)))]
+pub async fn list_pins(
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = message_service::list_pins(claims.sub, room_id, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<PinnedMessageResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
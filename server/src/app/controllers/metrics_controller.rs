@@ -0,0 +1,22 @@
+use actix_web::{HttpResponse, Responder, http::header::ContentType, web};
+
+use crate::{
+    app::request_error::{RequestError, RequestResult},
+    core::app_data::AppData,
+};
+
+#[utoipa::path(get, path = "/metrics", responses((status = 200, description = "metrics in Prometheus text format")))]
+pub async fn metrics(app_data: web::Data<AppData>) -> RequestResult<impl Responder> {
+    let app_data = app_data.into_inner();
+
+    app_data.metrics.observe_pool(&app_data.pool);
+
+    let body = app_data
+        .metrics
+        .encode()
+        .map_err(|e| RequestError::InternalServerError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::plaintext())
+        .body(body))
+}
@@ -1,2 +1,11 @@
+pub mod attachment_controller;
+pub mod health_controller;
+pub mod message_controller;
+pub mod metrics_controller;
+pub mod presence_controller;
 pub mod profile_controller;
+pub mod reaction_controller;
+pub mod room_controller;
+pub mod search_controller;
 pub mod user_controller;
+pub mod ws_controller;
@@ -0,0 +1,58 @@
+use actix_web::{HttpResponse, Responder, web};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::presence::{PresenceResponse, SetPresenceRequest},
+        request_error::RequestResult,
+        services::presence_service,
+    },
+    core::app_data::AppData,
+};
+
+#[tracing::instrument(name = "set_presence", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(put, path = "/me/presence", request_body = SetPresenceRequest, responses((status = 200, description = "presence of the caller", body = PresenceResponse)))]
+pub async fn set_presence(
+    presence: web::Json<SetPresenceRequest>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let presence = presence.into_inner().try_into()?;
+    let app_data = app_data.into_inner();
+
+    let response =
+        presence_service::set_presence(claims.sub, presence, &app_data.pool, &app_data.hub).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The presence has been successfully updated!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // PresenceResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "list_room_presence", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/rooms/{id}/presence", responses((status = 200, description = "presence of every member of the room", body = Vec<PresenceResponse>)))]
+pub async fn list_room_presence(
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response =
+        presence_service::list_room_presence(claims.sub, room_id, &app_data.pool, &app_data.hub)
+            .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<PresenceResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
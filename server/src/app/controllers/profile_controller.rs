@@ -0,0 +1,129 @@
+use actix_multipart::Multipart;
+use actix_web::{
+    HttpResponse, Responder,
+    http::header::{CacheControl, CacheDirective, X_CONTENT_TYPE_OPTIONS},
+    web,
+};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        controllers::attachment_controller,
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::profiles::{AvatarQuery, ProfileResponse},
+        request_error::{RequestError, RequestResult},
+        services::profile_service,
+    },
+    core::app_data::AppData,
+};
+
+// avatar URLs change with every upload, so browsers may keep a copy for a while
+const AVATAR_MAX_AGE: u32 = 24 * 60 * 60;
+
+#[tracing::instrument(name = "get_profile", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/users/{id}/profile", responses((status = 200, description = "profile of the user", body = ProfileResponse)))]
+pub async fn get_profile(
+    user_id: web::Path<Uuid>,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let user_id = user_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = profile_service::get_profile(user_id, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // ProfileResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "set_avatar", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(put, path = "/me/avatar", request_body(content_type = "multipart/form-data", description = "a single `file` part"), responses((status = 200, description = "profile of the caller with the new avatar", body = ProfileResponse)))]
+pub async fn set_avatar(
+    payload: Multipart,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let app_data = app_data.into_inner();
+    let mut files = attachment_controller::read_files(payload, &app_data.upload_limits).await?;
+    if files.len() != 1 {
+        return Err(RequestError::BadRequest(
+            "Exactly one `file` part is expected".into(),
+        ));
+    }
+
+    let response = profile_service::set_avatar(
+        claims.sub,
+        files.remove(0),
+        &app_data.upload_limits,
+        &app_data.pool,
+        app_data.storage.as_ref(),
+    )
+    .await;
+
+    match &response {
+        Ok(_) => tracing::info!("The avatar has been successfully updated!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // ProfileResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "delete_avatar", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(delete, path = "/me/avatar", responses((status = 200, description = "profile of the caller with the generated avatar", body = ProfileResponse)))]
+pub async fn delete_avatar(
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let app_data = app_data.into_inner();
+
+    let response =
+        profile_service::delete_avatar(claims.sub, &app_data.pool, app_data.storage.as_ref()).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The avatar has been successfully deleted!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // ProfileResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "get_avatar", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/users/{id}/avatar", params(AvatarQuery), responses((status = 200, description = "square avatar of the user", content_type = "image/png")))]
+pub async fn get_avatar(
+    user_id: web::Path<Uuid>,
+    query: web::Query<AvatarQuery>,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let user_id = user_id.into_inner();
+    let query = query.into_inner().try_into()?;
+    let app_data = app_data.into_inner();
+
+    let response =
+        profile_service::get_avatar(user_id, query, &app_data.pool, app_data.storage.as_ref())
+            .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    let (content_type, data) = response?;
+
+    // avatar contents, always JPEG or PNG
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(AVATAR_MAX_AGE),
+        ]))
+        .body(data))
+}
@@ -0,0 +1,72 @@
+use actix_web::{HttpResponse, Responder, web};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::reactions::ReactionResponse,
+        request_error::RequestResult,
+        services::reaction_service,
+    },
+    core::app_data::AppData,
+};
+
+#[tracing::instrument(name = "add_reaction", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(put, path = "/messages/{id}/reactions/{emoji}", responses((status = 201, description = "reaction added successfully", body = ReactionResponse)))]
+pub async fn add_reaction(
+    path: web::Path<(Uuid, String)>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let (message_id, emoji) = path.into_inner();
+    let emoji = emoji.try_into()?;
+    let app_data = app_data.into_inner();
+
+    let response = reaction_service::add_reaction(
+        claims.sub,
+        message_id,
+        emoji,
+        &app_data.pool,
+        &app_data.hub,
+    )
+    .await;
+
+    match &response {
+        Ok(_) => tracing::info!("The reaction has been successfully added!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // ReactionResponse
+    Ok(HttpResponse::Created().json(response?))
+}
+
+#[tracing::instrument(name = "remove_reaction", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(delete, path = "/messages/{id}/reactions/{emoji}", responses((status = 200, description = "reaction removed successfully", body = ReactionResponse)))]
+pub async fn remove_reaction(
+    path: web::Path<(Uuid, String)>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let (message_id, emoji) = path.into_inner();
+    let emoji = emoji.try_into()?;
+    let app_data = app_data.into_inner();
+
+    let response = reaction_service::remove_reaction(
+        claims.sub,
+        message_id,
+        emoji,
+        &app_data.pool,
+        &app_data.hub,
+    )
+    .await;
+
+    match &response {
+        Ok(_) => tracing::info!("The reaction has been successfully removed!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // ReactionResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
@@ -0,0 +1,175 @@
+use actix_web::{HttpResponse, Responder, web};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::rooms::{
+            CreateInviteRequest, CreateRoomRequest, MarkReadRequest, ReadReceiptResponse,
+            RoomInviteResponse, RoomResponse, UnreadCountResponse,
+        },
+        request_error::RequestResult,
+        services::room_service,
+    },
+    core::app_data::AppData,
+};
+
+#[tracing::instrument(name = "create_room", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/rooms", request_body = CreateRoomRequest, responses((status = 201, description = "room created successfully", body = RoomResponse)))]
+pub async fn create_room(
+    room: web::Json<CreateRoomRequest>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let room = room.into_inner().try_into()?;
+    let app_data = app_data.into_inner();
+
+    let response = room_service::create_room(claims.sub, room, &app_data.pool, &app_data.hub).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The room has been successfully created!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // RoomResponse
+    Ok(HttpResponse::Created().json(response?))
+}
+
+#[tracing::instrument(name = "list_rooms", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/rooms", responses((status = 200, description = "rooms of the caller", body = Vec<RoomResponse>)))]
+pub async fn list_rooms(
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let app_data = app_data.into_inner();
+
+    let response = room_service::list_rooms(claims.sub, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<RoomResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "join_room", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/rooms/{id}/members", responses((status = 200, description = "joined the room", body = RoomResponse)))]
+pub async fn join_room(
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response =
+        room_service::join_room(claims.sub, room_id, &app_data.pool, &app_data.hub).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The user has joined the room!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // RoomResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "leave_room", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(delete, path = "/rooms/{id}/members", responses((status = 200, description = "left the room")))]
+pub async fn leave_room(
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response =
+        room_service::leave_room(claims.sub, room_id, &app_data.pool, &app_data.hub).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The user has left the room!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // Uuid
+    Ok(HttpResponse::Ok().body(response?.to_string()))
+}
+
+#[tracing::instrument(name = "invite_user", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/rooms/{id}/invites", request_body = CreateInviteRequest, responses((status = 201, description = "user invited to the room", body = RoomInviteResponse)))]
+pub async fn invite_user(
+    invite: web::Json<CreateInviteRequest>,
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let invitee_id = invite.into_inner().user_id;
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = room_service::invite_user(claims.sub, room_id, invitee_id, &app_data.pool).await;
+
+    match &response {
+        Ok(_) => tracing::info!("The user has been invited to the room!"),
+        Err(e) => tracing::error!("Error: {}", e),
+    };
+
+    // RoomInviteResponse
+    Ok(HttpResponse::Created().json(response?))
+}
+
+#[tracing::instrument(name = "list_unread", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/rooms/unread", responses((status = 200, description = "unread message counts of every room of the caller", body = Vec<UnreadCountResponse>)))]
+pub async fn list_unread(
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let app_data = app_data.into_inner();
+
+    let response = room_service::list_unread(claims.sub, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<UnreadCountResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
+
+#[tracing::instrument(name = "mark_read", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/rooms/{id}/read", request_body = MarkReadRequest, responses((status = 200, description = "read marker of the caller", body = ReadReceiptResponse)))]
+pub async fn mark_read(
+    read: web::Json<MarkReadRequest>,
+    room_id: web::Path<Uuid>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let message_id = read.into_inner().message_id;
+    let room_id = room_id.into_inner();
+    let app_data = app_data.into_inner();
+
+    let response = room_service::mark_read(
+        claims.sub,
+        room_id,
+        message_id,
+        &app_data.pool,
+        &app_data.hub,
+    )
+    .await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // ReadReceiptResponse
+    Ok(HttpResponse::Ok().json(response?))
+}
@@ -0,0 +1,32 @@
+use actix_web::{HttpResponse, Responder, web};
+
+use crate::{
+    app::{
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::search::{SearchHitResponse, SearchMessagesQuery},
+        request_error::RequestResult,
+        services::search_service,
+    },
+    core::app_data::AppData,
+};
+
+#[tracing::instrument(name = "search_messages", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(get, path = "/search/messages", params(SearchMessagesQuery), responses((status = 200, description = "matching messages from the caller's rooms, newest first", body = Vec<SearchHitResponse>)))]
+pub async fn search_messages(
+    query: web::Query<SearchMessagesQuery>,
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let query = query.into_inner().try_into()?;
+    let app_data = app_data.into_inner();
+
+    let response = search_service::search_messages(claims.sub, query, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // Vec<SearchHitResponse>
+    Ok(HttpResponse::Ok().json(response?))
+}
@@ -0,0 +1,66 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+
+use crate::{
+    app::{
+        middlewares::{jwt::Claims, request_id::RequestId},
+        models::ws_tickets::{ConnectQuery, WsTicketResponse},
+        realtime::session,
+        request_error::{RequestError, RequestResult},
+        services::ws_ticket_service,
+    },
+    core::app_data::AppData,
+};
+
+const MAX_FRAME_SIZE: usize = 64 * 1024;
+
+#[tracing::instrument(name = "create_ws_ticket", skip_all, fields(request_id = %request_id))]
+#[utoipa::path(post, path = "/ws/tickets", responses((status = 201, description = "single-use ticket for the WebSocket handshake", body = WsTicketResponse)))]
+pub async fn create_ticket(
+    claims: Claims,
+    app_data: web::Data<AppData>,
+    request_id: RequestId,
+) -> RequestResult<impl Responder> {
+    let response = ws_ticket_service::issue_ticket(claims.sub, &app_data.pool).await;
+
+    if let Err(e) = &response {
+        tracing::error!("Error: {}", e);
+    }
+
+    // WsTicketResponse
+    Ok(HttpResponse::Created().json(response?))
+}
+
+// browsers cannot set headers on a WebSocket handshake, so it carries a ticket from
+// `create_ticket` instead of the bearer token
+#[utoipa::path(get, path = "/ws", params(ConnectQuery), responses((status = 101, description = "switching to the WebSocket protocol")))]
+pub async fn connect(
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<ConnectQuery>,
+    app_data: web::Data<AppData>,
+) -> RequestResult<HttpResponse> {
+    if app_data.hub.is_closing() {
+        return Err(RequestError::ServiceUnavailable(
+            "server is restarting".into(),
+        ));
+    }
+
+    let user_id = ws_ticket_service::redeem_ticket(&query.ticket, &app_data.pool).await?;
+
+    let (response, ws_session, stream) =
+        actix_ws::handle(&req, body).map_err(|e| RequestError::BadRequest(e.to_string()))?;
+
+    let stream = stream
+        .max_frame_size(MAX_FRAME_SIZE)
+        .aggregate_continuations()
+        .max_continuation_size(MAX_FRAME_SIZE);
+
+    actix_web::rt::spawn(session::run(
+        ws_session,
+        stream,
+        user_id,
+        app_data.get_ref().clone(),
+    ));
+
+    Ok(response)
+}
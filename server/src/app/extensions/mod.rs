@@ -1,2 +1,3 @@
 pub mod jwt_coding;
 pub mod request_error;
+pub mod url_signing;
@@ -1,7 +1,7 @@
-use actix_web::{
-    HttpResponse, ResponseError,
-    http::{StatusCode, header::ContentType},
-};
+use actix_web::{HttpResponse, ResponseError, http::StatusCode};
+use serde::Serialize;
+
+use crate::{app::middlewares::request_id::RequestId, core::storage::StorageError};
 
 pub type RequestResult<T> = Result<T, RequestError>;
 
@@ -25,6 +25,12 @@ pub enum RequestError {
     #[error("409 Conflict. Context: {0}")]
     Conflict(String),
 
+    #[error("413 Payload Too Large. Context: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("415 Unsupported Media Type. Context: {0}")]
+    UnsupportedMediaType(String),
+
     #[error("422 Unprocessable Entity. Context: {0}")]
     UnprocessableEntity(String),
 
@@ -41,11 +47,21 @@ pub enum RequestError {
     ServiceUnavailable(String),
 }
 
+#[derive(Serialize)]
+struct ErrorBody {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
 impl ResponseError for RequestError {
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code())
-            .insert_header(ContentType::plaintext())
-            .body(self.to_string())
+        let body = ErrorBody {
+            message: self.to_string(),
+            request_id: RequestId::current().map(|id| id.to_string()),
+        };
+
+        HttpResponse::build(self.status_code()).json(body)
     }
 
     fn status_code(&self) -> StatusCode {
@@ -56,6 +72,8 @@ impl ResponseError for RequestError {
             RequestError::NotFound(_) => StatusCode::NOT_FOUND,
             RequestError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
             RequestError::Conflict(_) => StatusCode::CONFLICT,
+            RequestError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            RequestError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
             RequestError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
 
             RequestError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -98,3 +116,21 @@ impl From<jsonwebtoken::errors::Error> for RequestError {
         Self::Unauthorized(value.to_string())
     }
 }
+
+impl From<StorageError> for RequestError {
+    fn from(value: StorageError) -> Self {
+        match value {
+            StorageError::NotFound(_) => Self::NotFound(value.to_string()),
+            StorageError::IoError(_) => Self::InternalServerError(value.to_string()),
+            StorageError::HttpError(_) | StorageError::UnexpectedStatus { .. } => {
+                Self::BadGateway(value.to_string())
+            }
+        }
+    }
+}
+
+impl From<actix_multipart::MultipartError> for RequestError {
+    fn from(value: actix_multipart::MultipartError) -> Self {
+        Self::BadRequest(value.to_string())
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::app::request_error::{RequestError, RequestResult};
+
+// `path?user=...&expires=...&signature=...`, good for `ttl` and for `user_id` only
+pub fn sign_url(path: &str, user_id: Uuid, ttl: Duration, secret: &str) -> (String, DateTime<Utc>) {
+    let expires_at = Utc::now() + ttl;
+    let expires = expires_at.timestamp();
+    let signature = hex::encode(mac(path, user_id, expires, secret).finalize().into_bytes());
+
+    (
+        format!("{path}?user={user_id}&expires={expires}&signature={signature}"),
+        expires_at,
+    )
+}
+
+pub fn verify_url(
+    path: &str,
+    user_id: Uuid,
+    expires: i64,
+    signature: &str,
+    secret: &str,
+) -> RequestResult<()> {
+    let invalid = || RequestError::Forbidden("Invalid or expired signature".into());
+
+    if expires <= Utc::now().timestamp() {
+        return Err(invalid());
+    }
+    let signature = hex::decode(signature).map_err(|_| invalid())?;
+
+    mac(path, user_id, expires, secret)
+        .verify_slice(&signature)
+        .map_err(|_| invalid())
+}
+
+fn mac(path: &str, user_id: Uuid, expires: i64, secret: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{path}\n{user_id}\n{expires}").as_bytes());
+
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_urls() {
+        let user_id = Uuid::new_v4();
+        let (url, expires_at) = sign_url("/files/a", user_id, Duration::minutes(1), "secret");
+
+        let query = url.strip_prefix("/files/a?").unwrap();
+        let params = query
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+            .collect::<Vec<_>>();
+        assert_eq!(params[0], ("user", user_id.to_string().as_str()));
+        assert_eq!(params[1].1, expires_at.timestamp().to_string());
+        let (expires, signature) = (expires_at.timestamp(), params[2].1);
+
+        assert!(verify_url("/files/a", user_id, expires, signature, "secret").is_ok());
+
+        // anything else the signature covers
+        for (path, user, expires, secret) in [
+            ("/files/b", user_id, expires, "secret"),
+            ("/files/a", Uuid::new_v4(), expires, "secret"),
+            ("/files/a", user_id, expires + 60, "secret"),
+            ("/files/a", user_id, expires, "other"),
+        ] {
+            let result = verify_url(path, user, expires, signature, secret);
+            assert!(matches!(result, Err(RequestError::Forbidden(_))));
+        }
+
+        let (url, expires_at) = sign_url("/files/a", user_id, Duration::zero(), "secret");
+        let signature = url.rsplit_once("signature=").unwrap().1;
+        let result = verify_url(
+            "/files/a",
+            user_id,
+            expires_at.timestamp(),
+            signature,
+            "secret",
+        );
+        assert!(matches!(result, Err(RequestError::Forbidden(_))));
+        let result = verify_url("/files/a", user_id, expires, "not hex", "secret");
+        assert!(matches!(result, Err(RequestError::Forbidden(_))));
+    }
+}
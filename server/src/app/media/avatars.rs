@@ -0,0 +1,135 @@
+use std::io::Cursor;
+
+use bytes::Bytes;
+use image::{ImageFormat, ImageResult, Rgb, RgbImage, imageops::FilterType};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::images;
+
+// name and edge of every stored avatar, smaller pictures are scaled up
+pub const AVATAR_SIZES: [(&str, u32); 3] = [("small", 64), ("medium", 128), ("large", 256)];
+pub const DEFAULT_AVATAR_SIZE: &str = "medium";
+
+// identicons are a mirrored grid of cells on a light background
+const IDENTICON_CELLS: u32 = 5;
+const IDENTICON_BACKGROUND: Rgb<u8> = Rgb([240, 240, 240]);
+
+pub struct Avatar {
+    pub size: &'static str,
+    pub content_type: &'static str,
+    pub data: Bytes,
+}
+
+// CPU heavy, run it on a blocking thread
+pub fn crop(data: &Bytes) -> ImageResult<Vec<Avatar>> {
+    let (image, _, _) = images::decode(data)?;
+
+    // keep the centre of the picture
+    let side = image.width().min(image.height());
+    let square = image.crop_imm(
+        (image.width() - side) / 2,
+        (image.height() - side) / 2,
+        side,
+        side,
+    );
+
+    AVATAR_SIZES
+        .into_iter()
+        .map(|(size, edge)| {
+            let resized = square.resize_exact(edge, edge, FilterType::Lanczos3);
+            let (content_type, data) = images::encode_preview(&resized)?;
+
+            Ok(Avatar {
+                size,
+                content_type,
+                data,
+            })
+        })
+        .collect()
+}
+
+// the same user always gets the same PNG
+pub fn identicon(user_id: Uuid, edge: u32) -> ImageResult<Bytes> {
+    let hash = Sha256::digest(user_id.as_bytes());
+
+    // muted colours, never too dark or too close to the background
+    let color = Rgb([hash[0] / 2 + 48, hash[1] / 2 + 48, hash[2] / 2 + 48]);
+    let half = IDENTICON_CELLS.div_ceil(2);
+    let filled = |column: u32, row: u32| {
+        let column = column.min(IDENTICON_CELLS - 1 - column);
+        let bit = row * half + column;
+        hash[3 + (bit / 8) as usize] >> (bit % 8) & 1 == 1
+    };
+
+    // a margin of half a cell on every side
+    let cell = edge as f32 / (IDENTICON_CELLS + 1) as f32;
+    let image = RgbImage::from_fn(edge, edge, |x, y| {
+        // sampled at the pixel centre so both halves stay symmetric
+        let column = ((x as f32 + 0.5) / cell - 0.5).floor();
+        let row = ((y as f32 + 0.5) / cell - 0.5).floor();
+        let inside = (0.0..IDENTICON_CELLS as f32).contains(&column)
+            && (0.0..IDENTICON_CELLS as f32).contains(&row);
+
+        match inside && filled(column as u32, row as u32) {
+            true => color,
+            false => IDENTICON_BACKGROUND,
+        }
+    });
+
+    let mut data = Vec::new();
+    image.write_to(&mut Cursor::new(&mut data), ImageFormat::Png)?;
+
+    Ok(data.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView, Rgba, RgbaImage};
+
+    use super::*;
+
+    #[test]
+    fn test_crop_and_identicon() {
+        // a wide picture with transparent borders left and right
+        let image = RgbaImage::from_fn(300, 100, |x, _| match (100..200).contains(&x) {
+            true => Rgba([0, 0, 255, 255]),
+            false => Rgba([0, 0, 0, 0]),
+        });
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let avatars = crop(&png.into()).unwrap();
+        let sizes = avatars
+            .iter()
+            .map(|avatar| {
+                let image = image::load_from_memory(&avatar.data).unwrap();
+                (avatar.size, image.dimensions(), avatar.content_type)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            vec![
+                ("small", (64, 64), "image/png"),
+                ("medium", (128, 128), "image/png"),
+                ("large", (256, 256), "image/png"),
+            ]
+        );
+        // only the opaque centre is left
+        let small = image::load_from_memory(&avatars[0].data).unwrap();
+        assert_eq!(small.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+
+        let user_id = Uuid::new_v4();
+        let generated = identicon(user_id, 64).unwrap();
+        assert_eq!(generated, identicon(user_id, 64).unwrap());
+        assert_ne!(generated, identicon(Uuid::new_v4(), 64).unwrap());
+
+        // mirrored around the vertical axis
+        let image = image::load_from_memory(&generated).unwrap().to_rgb8();
+        for (x, y) in [(12, 12), (20, 30), (25, 50)] {
+            assert_eq!(image.get_pixel(x, y), image.get_pixel(63 - x, y));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::io::Cursor;
+
+use bytes::Bytes;
+use image::{
+    DynamicImage, ImageDecoder, ImageFormat, ImageReader, ImageResult, Limits,
+    codecs::jpeg::JpegEncoder, metadata::Orientation,
+};
+use img_parts::{DynImage, ImageEXIF};
+
+// name and longest edge of each thumbnail, images already that small get none
+pub const THUMBNAIL_SIZES: [(&str, u32); 2] = [("small", 320), ("large", 1280)];
+
+// decompression bombs are rejected before any pixels are allocated
+const MAX_DIMENSION: u32 = 16_384;
+const MAX_DECODE_BYTES: u64 = 256 * 1024 * 1024;
+
+const JPEG_QUALITY: u8 = 85;
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+const BLURHASH_SOURCE_SIZE: u32 = 32;
+
+pub struct ProcessedImage {
+    // as displayed, after the EXIF orientation is applied
+    pub width: u32,
+    pub height: u32,
+    pub blurhash: Option<String>,
+    // replaces the original, None when it carried no EXIF metadata
+    pub stripped: Option<Bytes>,
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+pub struct Thumbnail {
+    pub size: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub content_type: &'static str,
+    pub data: Bytes,
+}
+
+// CPU heavy, run it on a blocking thread
+pub fn process(data: &Bytes) -> ImageResult<ProcessedImage> {
+    let (image, orientation, format) = decode(data)?;
+
+    // dropping the EXIF block in place keeps the original bytes, but a rotated
+    // image has to be re-encoded or it would lose its orientation
+    let stripped = match (orientation, format) {
+        (Orientation::NoTransforms, _) | (_, None) => strip_exif(data),
+        (_, Some(format)) => Some(encode(&image, format)?),
+    };
+
+    let thumbnails = THUMBNAIL_SIZES
+        .into_iter()
+        .filter(|(_, edge)| image.width().max(image.height()) > *edge)
+        .map(|(size, edge)| thumbnail(&image, size, edge))
+        .collect::<ImageResult<Vec<_>>>()?;
+
+    let source = image
+        .thumbnail(BLURHASH_SOURCE_SIZE, BLURHASH_SOURCE_SIZE)
+        .to_rgba8();
+    let blurhash = blurhash::encode(
+        BLURHASH_COMPONENTS.0,
+        BLURHASH_COMPONENTS.1,
+        source.width(),
+        source.height(),
+        source.as_raw(),
+    )
+    .ok();
+
+    Ok(ProcessedImage {
+        width: image.width(),
+        height: image.height(),
+        blurhash,
+        stripped,
+        thumbnails,
+    })
+}
+
+// returns the image as displayed, with the EXIF orientation already applied
+pub fn decode(data: &Bytes) -> ImageResult<(DynamicImage, Orientation, Option<ImageFormat>)> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+
+    let mut reader = ImageReader::new(Cursor::new(data.as_ref())).with_guessed_format()?;
+    reader.limits(limits);
+    let format = reader.format();
+
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    Ok((image, orientation, format))
+}
+
+fn strip_exif(data: &Bytes) -> Option<Bytes> {
+    let mut image = DynImage::from_bytes(data.clone()).ok().flatten()?;
+    image.exif()?;
+    image.set_exif(None);
+
+    Some(image.encoder().bytes())
+}
+
+fn thumbnail(image: &DynamicImage, size: &'static str, edge: u32) -> ImageResult<Thumbnail> {
+    let resized = image.thumbnail(edge, edge);
+    let (content_type, data) = encode_preview(&resized)?;
+
+    Ok(Thumbnail {
+        size,
+        width: resized.width(),
+        height: resized.height(),
+        content_type,
+        data,
+    })
+}
+
+// JPEG, or PNG when the image has an alpha channel JPEG cannot carry
+pub fn encode_preview(image: &DynamicImage) -> ImageResult<(&'static str, Bytes)> {
+    let (format, content_type) = match image.color().has_alpha() {
+        true => (ImageFormat::Png, "image/png"),
+        false => (ImageFormat::Jpeg, "image/jpeg"),
+    };
+
+    Ok((content_type, encode(image, format)?))
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> ImageResult<Bytes> {
+    let mut data = Vec::new();
+
+    match format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY)
+            .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8()))?,
+        format => image.write_to(&mut Cursor::new(&mut data), format)?,
+    }
+
+    Ok(data.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+    use img_parts::jpeg::Jpeg;
+
+    use super::*;
+
+    fn jpeg(width: u32, height: u32) -> Bytes {
+        let image = RgbImage::from_fn(width, height, |x, y| Rgb([x as u8, y as u8, 128]));
+        encode(&DynamicImage::ImageRgb8(image), ImageFormat::Jpeg).unwrap()
+    }
+
+    fn with_exif(data: Bytes, orientation: u8) -> Bytes {
+        // big-endian TIFF header with a single IFD entry: Orientation (0x0112), SHORT
+        let mut exif = *b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\0\0\0\0\0\0\0";
+        exif[19] = orientation;
+        let mut image = Jpeg::from_bytes(data).unwrap();
+        image.set_exif(Some(Bytes::copy_from_slice(&exif)));
+        image.encoder().bytes()
+    }
+
+    fn has_exif(data: &Bytes) -> bool {
+        Jpeg::from_bytes(data.clone()).unwrap().exif().is_some()
+    }
+
+    #[test]
+    fn test_process_strips_exif_and_keeps_orientation() {
+        let upright = with_exif(jpeg(400, 200), 1);
+        let processed = process(&upright).unwrap();
+        let stripped = processed.stripped.unwrap();
+        assert!(has_exif(&upright) && !has_exif(&stripped));
+        assert_eq!((processed.width, processed.height), (400, 200));
+
+        // orientation 6 is "rotate 90° clockwise"
+        let rotated = with_exif(jpeg(400, 200), 6);
+        let processed = process(&rotated).unwrap();
+        assert!(!has_exif(&processed.stripped.unwrap()));
+        assert_eq!((processed.width, processed.height), (200, 400));
+        assert_eq!(processed.blurhash.unwrap().len(), 28);
+
+        let thumbnails = processed
+            .thumbnails
+            .iter()
+            .map(|t| (t.size, t.width, t.height, t.content_type))
+            .collect::<Vec<_>>();
+        assert_eq!(thumbnails, vec![("small", 160, 320, "image/jpeg")]);
+
+        assert!(process(&jpeg(64, 64)).unwrap().stripped.is_none());
+        assert!(process(&Bytes::from_static(b"not an image")).is_err());
+    }
+}
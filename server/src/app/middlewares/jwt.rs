@@ -1,7 +1,10 @@
+use std::future::{Ready, ready};
+
 use actix_web::{
-    Error,
+    Error, FromRequest, HttpMessage, HttpRequest,
     body::MessageBody,
-    dev::{ServiceRequest, ServiceResponse},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::header,
     middleware::Next,
     web,
 };
@@ -14,7 +17,7 @@ use crate::{
     core::app_data::AppData,
 };
 
-#[derive(Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Claims {
     pub sub: Uuid,     // subject
     pub email: String, // user email
@@ -22,15 +25,15 @@ pub struct Claims {
     pub exp: usize,    // expiration time
 }
 
-impl From<UserEntity> for Claims {
-    fn from(value: UserEntity) -> Self {
+impl Claims {
+    pub fn new(user: UserEntity, ttl: Duration) -> Self {
         let now = Utc::now();
 
         Self {
-            sub: value.id,
-            email: value.email,
+            sub: user.id,
+            email: user.email,
             iat: now.timestamp() as usize,
-            exp: (now + Duration::hours(24)).timestamp() as usize,
+            exp: (now + ttl).timestamp() as usize,
         }
     }
 }
@@ -49,7 +52,7 @@ pub async fn verify_jwt(
     let error_str = "Extract token error";
     let token = req
         .headers()
-        .get("Authorization")
+        .get(header::AUTHORIZATION)
         .ok_or(RequestError::Unauthorized(error_str.into()))?
         .to_str()
         .map_err(|e| RequestError::Unauthorized(e.to_string()))?
@@ -57,7 +60,29 @@ pub async fn verify_jwt(
         .filter(|t| !t.trim().is_empty())
         .ok_or(RequestError::Unauthorized(error_str.into()))?;
 
-    let _data = jwt_coding::decode_jwt::<Claims>(token, &app_data.jwt_secret)?;
+    let data = jwt_coding::decode_jwt::<Claims>(token, &app_data.jwt_secret)?;
+
+    // a token outlives a disabled user
+    if app_data.disabled_users.contains(&data.claims.sub) {
+        return Err(RequestError::Forbidden("User is disabled".into()).into());
+    }
+    req.extensions_mut().insert(data.claims);
 
     next.call(req).await
 }
+
+// available in handlers behind `verify_jwt`
+impl FromRequest for Claims {
+    type Error = RequestError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let claims = req
+            .extensions()
+            .get::<Claims>()
+            .cloned()
+            .ok_or(RequestError::Unauthorized("Missing token claims".into()));
+
+        ready(claims)
+    }
+}
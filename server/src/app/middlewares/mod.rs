@@ -1 +1,3 @@
 pub mod jwt;
+pub mod metrics;
+pub mod request_id;
@@ -0,0 +1,199 @@
+use std::{
+    convert::Infallible,
+    fmt,
+    future::{Ready, ready},
+};
+
+use actix_web::{
+    Error, FromRequest, HttpMessage, HttpRequest,
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+};
+use tracing_actix_web::RootSpan;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    // id of the request being handled by the current task, if any
+    pub fn current() -> Option<Self> {
+        CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+    }
+
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+
+        let is_valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LENGTH
+            && value.chars().all(|c| c.is_ascii_graphic());
+
+        is_valid.then(|| Self(value.to_owned()))
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for RequestId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(RequestId::generate);
+
+        ready(Ok(request_id))
+    }
+}
+
+pub async fn propagate_request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(RequestId::from_header)
+        .unwrap_or_else(RequestId::generate);
+
+    req.extensions_mut().insert(request_id.clone());
+
+    // replaces the id generated by TracingLogger on the root span
+    if let Some(root_span) = req.extensions().get::<RootSpan>() {
+        root_span.record("request_id", tracing::field::display(&request_id));
+    }
+
+    let result = CURRENT_REQUEST_ID
+        .scope(request_id.clone(), next.call(req))
+        .await;
+
+    match result {
+        Ok(mut res) => {
+            insert_header(res.headers_mut(), &request_id);
+            Ok(res)
+        }
+        Err(e) => {
+            // errors raised by inner middlewares are rendered here so the body carries the id
+            let mut response =
+                CURRENT_REQUEST_ID.sync_scope(request_id.clone(), || e.error_response());
+            insert_header(response.headers_mut(), &request_id);
+
+            Err(InternalError::from_response(e, response).into())
+        }
+    }
+}
+
+fn insert_header(headers: &mut HeaderMap, request_id: &RequestId) {
+    if let Ok(value) = HeaderValue::from_str(request_id.as_ref()) {
+        headers.insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, HttpResponse, middleware::from_fn, test, web};
+    use expect_test::expect;
+
+    use super::*;
+    use crate::app::request_error::{RequestError, RequestResult};
+
+    async fn echo(request_id: RequestId) -> HttpResponse {
+        HttpResponse::Ok().body(request_id.to_string())
+    }
+
+    async fn fail() -> RequestResult<HttpResponse> {
+        Err(RequestError::NotFound("missing".into()))
+    }
+
+    #[actix_web::test]
+    async fn test_incoming_request_id_is_propagated() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(propagate_request_id))
+                .route("/echo", web::get().to(echo))
+                .route("/fail", web::get().to(fail)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/echo")
+            .insert_header((REQUEST_ID_HEADER, "abc-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let exp = expect!["abc-123"];
+        exp.assert_eq(
+            res.headers()
+                .get(REQUEST_ID_HEADER)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+        );
+        let exp = expect!["abc-123"];
+        exp.assert_eq(std::str::from_utf8(&test::read_body(res).await).unwrap());
+
+        let req = test::TestRequest::get()
+            .uri("/fail")
+            .insert_header((REQUEST_ID_HEADER, "abc-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let exp =
+            expect![[r#"{"message":"404 Not Found. Context: missing","request_id":"abc-123"}"#]];
+        exp.assert_eq(std::str::from_utf8(&test::read_body(res).await).unwrap());
+    }
+
+    #[actix_web::test]
+    async fn test_invalid_request_id_is_replaced() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(propagate_request_id))
+                .route("/echo", web::get().to(echo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/echo")
+            .insert_header((REQUEST_ID_HEADER, "has spaces"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let header = res
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        assert!(Uuid::parse_str(&header).is_ok());
+        assert_eq!(
+            header,
+            std::str::from_utf8(&test::read_body(res).await).unwrap()
+        );
+    }
+}
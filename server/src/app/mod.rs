@@ -1,8 +1,11 @@
 pub mod controllers;
 pub mod extensions;
+pub mod media;
 pub mod middlewares;
 pub mod models;
+pub mod realtime;
 pub mod repositories;
+pub mod rich_text;
 pub mod routers;
 pub mod services;
 
@@ -0,0 +1,60 @@
+use actix_web::mime::{APPLICATION_OCTET_STREAM, Mime};
+use bytes::Bytes;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    app::request_error::{RequestError, RequestResult},
+    core::app_config::UploadLimits,
+};
+
+use super::domain;
+
+pub const MAX_FILES_PER_UPLOAD: usize = 10;
+
+// one `file` part of a multipart upload
+pub struct UploadedFile {
+    pub filename: String,
+    pub content_type: Option<Mime>,
+    pub data: Bytes,
+}
+
+pub struct ValidUploadedFile {
+    pub filename: domain::AttachmentFilename,
+    pub content_type: Mime,
+    pub data: Bytes,
+}
+
+impl UploadedFile {
+    pub fn validate(self, limits: &UploadLimits) -> RequestResult<ValidUploadedFile> {
+        let content_type = self.content_type.unwrap_or(APPLICATION_OCTET_STREAM);
+
+        if self.data.len() > limits.max_size_bytes {
+            return Err(RequestError::PayloadTooLarge(format!(
+                "Files may not be larger than {} bytes",
+                limits.max_size_bytes
+            )));
+        }
+        if !limits.is_allowed(&content_type) {
+            return Err(RequestError::UnsupportedMediaType(format!(
+                "Files of type {} are not accepted",
+                content_type.essence_str()
+            )));
+        }
+
+        Ok(ValidUploadedFile {
+            filename: self.filename.try_into()?,
+            content_type,
+            data: self.data,
+        })
+    }
+}
+
+// the query of a URL from `sign_attachment_url` or `sign_thumbnail_url`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SignedUrlQuery {
+    pub user: Uuid,
+    pub expires: i64,
+    pub signature: String,
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AttachmentEntity {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    pub message_id: Option<Uuid>,
+    pub uploaded_by: Option<Uuid>,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+    pub created_at: DateTime<Utc>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+impl AttachmentEntity {
+    // images keep their EXIF metadata until the image worker has been through them
+    pub fn is_pending_image(&self) -> bool {
+        self.processed_at.is_none() && self.content_type.starts_with("image/")
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ThumbnailEntity {
+    pub attachment_id: Uuid,
+    pub size: String,
+    pub width: i32,
+    pub height: i32,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ThumbnailResponse {
+    pub size: String,
+    pub width: i32,
+    pub height: i32,
+    pub content_type: String,
+    pub url: String,
+}
+
+impl From<ThumbnailEntity> for ThumbnailResponse {
+    fn from(value: ThumbnailEntity) -> Self {
+        Self {
+            url: format!(
+                "/attachments/{}/thumbnails/{}",
+                value.attachment_id, value.size
+            ),
+            size: value.size,
+            width: value.width,
+            height: value.height,
+            content_type: value.content_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AttachmentResponse {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    // None until the attachment is sent with a message
+    pub message_id: Option<Uuid>,
+    pub uploaded_by: Option<Uuid>,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    // needs the bearer token; `POST {url}/signed-url` gives one that works without it,
+    // e.g. in an <img> tag
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+    // images only, filled in shortly after the upload by the image worker
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub thumbnails: Vec<ThumbnailResponse>,
+}
+
+impl From<AttachmentEntity> for AttachmentResponse {
+    fn from(value: AttachmentEntity) -> Self {
+        Self::new(value, Vec::new())
+    }
+}
+
+impl AttachmentResponse {
+    pub fn new(value: AttachmentEntity, thumbnails: Vec<ThumbnailEntity>) -> Self {
+        Self {
+            url: format!("/attachments/{}", value.id),
+            id: value.id,
+            room_id: value.room_id,
+            message_id: value.message_id,
+            uploaded_by: value.uploaded_by,
+            filename: value.filename,
+            content_type: value.content_type,
+            size_bytes: value.size_bytes,
+            created_at: value.created_at,
+            width: value.width,
+            height: value.height,
+            blurhash: value.blurhash,
+            thumbnails: thumbnails.into_iter().map(From::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignedUrlResponse {
+    // only for the user who asked for it
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,53 @@
+use crate::app::request_error::RequestError;
+
+const MAX_FILENAME_LENGTH: usize = 255;
+
+#[derive(Debug, Clone)]
+pub struct AttachmentFilename(String);
+
+impl TryFrom<String> for AttachmentFilename {
+    type Error = RequestError;
+
+    // keeps only the last path component, some browsers send the full client path
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let name = value
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect::<String>();
+        let name = name.trim();
+
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(RequestError::BadRequest("File name is empty".into()));
+        }
+        if name.chars().count() > MAX_FILENAME_LENGTH {
+            return Err(RequestError::BadRequest("File name is too long".into()));
+        }
+
+        Ok(Self(name.to_owned()))
+    }
+}
+
+impl AsRef<str> for AttachmentFilename {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attachment_filename() {
+        let name = |value: &str| AttachmentFilename::try_from(value.to_string());
+
+        assert_eq!(name("C:\\Users\\me\\cat.png").unwrap().as_ref(), "cat.png");
+        assert_eq!(name("../../etc/passwd").unwrap().as_ref(), "passwd");
+        assert_eq!(name(" report\n.pdf ").unwrap().as_ref(), "report.pdf");
+        assert!(name("photos/").is_err());
+        assert!(name("..").is_err());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod attachment_request;
+pub mod attachment_response;
+pub mod domain;
+
+pub use attachment_request::*;
+pub use attachment_response::*;
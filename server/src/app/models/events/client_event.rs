@@ -0,0 +1,46 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+// frames accepted from WebSocket clients, e.g.
+// {"type": "send_message", "payload": {"room_id": "...", "content": "hi"}}
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum ClientEvent {
+    SendMessage {
+        room_id: Uuid,
+        content: String,
+        #[serde(default)]
+        parent_id: Option<Uuid>,
+        #[serde(default)]
+        attachment_ids: Vec<Uuid>,
+    },
+    EditMessage {
+        message_id: Uuid,
+        content: String,
+    },
+    DeleteMessage {
+        message_id: Uuid,
+    },
+    AddReaction {
+        message_id: Uuid,
+        emoji: String,
+    },
+    RemoveReaction {
+        message_id: Uuid,
+        emoji: String,
+    },
+    Typing {
+        room_id: Uuid,
+    },
+    StopTyping {
+        room_id: Uuid,
+    },
+    // sent by clients when the user goes idle on that device or comes back
+    SetAway {
+        away: bool,
+    },
+    MarkRead {
+        room_id: Uuid,
+        message_id: Uuid,
+    },
+}
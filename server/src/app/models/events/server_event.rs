@@ -0,0 +1,48 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::app::models::{
+    attachments::AttachmentResponse,
+    messages::{MessageResponse, PinnedMessageResponse},
+    presence::PresenceResponse,
+    reactions::ReactionResponse,
+    rooms::ReadReceiptResponse,
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum ServerEvent {
+    MessageCreated(MessageResponse),
+    MessageEdited(MessageResponse),
+    // something derived from the message changed, such as its link previews;
+    // reactions are left out
+    MessageUpdated(MessageResponse),
+    MessageDeleted(MessageResponse),
+    ThreadReplyCreated(MessageResponse),
+    // the parent message with fresh reply_count / last_reply_at
+    ThreadUpdated(MessageResponse),
+    MessagePinned(PinnedMessageResponse),
+    MessageUnpinned(PinnedMessageResponse),
+    ReactionAdded(ReactionResponse),
+    ReactionRemoved(ReactionResponse),
+    // sent only to the mentioned user
+    Mentioned(MessageResponse),
+    MessagesRead(ReadReceiptResponse),
+    // dimensions, blurhash and thumbnails of an image are ready
+    AttachmentUpdated(AttachmentResponse),
+    // ephemeral, never stored
+    Typing {
+        room_id: Uuid,
+        user_id: Uuid,
+        expires_in_ms: u64,
+    },
+    TypingStopped {
+        room_id: Uuid,
+        user_id: Uuid,
+    },
+    // sent to the user and everyone sharing a room with them
+    PresenceChanged(PresenceResponse),
+    Error {
+        message: String,
+    },
+}
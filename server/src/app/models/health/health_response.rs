@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+impl ComponentHealth {
+    pub fn up() -> Self {
+        Self {
+            status: HealthStatus::Up,
+            details: None,
+        }
+    }
+
+    pub fn down(details: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Down,
+            details: Some(details.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: HealthStatus,
+    pub components: BTreeMap<&'static str, ComponentHealth>,
+}
+
+impl HealthResponse {
+    pub fn from_components(components: BTreeMap<&'static str, ComponentHealth>) -> Self {
+        let status = match components.values().all(|c| c.status == HealthStatus::Up) {
+            true => HealthStatus::Up,
+            false => HealthStatus::Down,
+        };
+
+        Self { status, components }
+    }
+}
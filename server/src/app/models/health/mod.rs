@@ -0,0 +1,3 @@
+pub mod health_response;
+
+pub use health_response::*;
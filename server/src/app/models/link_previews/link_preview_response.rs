@@ -0,0 +1,37 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// a preview together with the message linking it
+#[derive(Debug, FromRow)]
+pub struct MessageLinkPreviewEntity {
+    pub message_id: Uuid,
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LinkPreviewResponse {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    // points at the linked site, clients load it from there
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+impl From<MessageLinkPreviewEntity> for LinkPreviewResponse {
+    fn from(value: MessageLinkPreviewEntity) -> Self {
+        Self {
+            url: value.url,
+            title: value.title,
+            description: value.description,
+            image_url: value.image_url,
+            site_name: value.site_name,
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod link_preview_response;
+
+pub use link_preview_response::*;
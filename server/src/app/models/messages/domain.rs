@@ -0,0 +1,159 @@
+use crate::app::{request_error::RequestError, rich_text};
+
+const MAX_MESSAGE_LENGTH: usize = 4000;
+const MAX_MENTIONS: usize = 50;
+const MAX_LINKS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct MessageContent(String);
+
+impl TryFrom<String> for MessageContent {
+    type Error = RequestError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.trim().is_empty() {
+            return Err(RequestError::BadRequest("Message is empty".into()));
+        }
+
+        Self::caption(value)
+    }
+}
+
+impl MessageContent {
+    // text sent along with attachments, which may be empty
+    pub fn caption(value: String) -> Result<Self, RequestError> {
+        if value.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(RequestError::BadRequest("Message is too long".into()));
+        }
+
+        Ok(Self(value))
+    }
+
+    // distinct `@username` tokens; an `@` right after a letter or digit (as in an
+    // e-mail address) does not start a mention
+    pub fn mentions(&self) -> Vec<String> {
+        let mut mentions = Vec::new();
+        let mut previous = None;
+
+        for (i, c) in self.0.char_indices() {
+            let starts_mention = c == '@' && !previous.is_some_and(char::is_alphanumeric);
+            previous = Some(c);
+            if !starts_mention {
+                continue;
+            }
+
+            let rest = &self.0[i + 1..];
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric())
+                .unwrap_or(rest.len());
+            let username = &rest[..end];
+
+            if !username.is_empty() && !mentions.iter().any(|m| m == username) {
+                mentions.push(username.to_owned());
+            }
+            if mentions.len() == MAX_MENTIONS {
+                break;
+            }
+        }
+
+        mentions
+    }
+
+    // distinct http(s) links in order of appearance, the first few get a preview
+    pub fn links(&self) -> Vec<String> {
+        let mut links = Vec::new();
+
+        for word in self.0.split_whitespace() {
+            let Some(start) = word.find("http://").or_else(|| word.find("https://")) else {
+                continue;
+            };
+            let Some((url, _)) = rich_text::link_at(&word[start..]) else {
+                continue;
+            };
+            let link = String::from(url);
+            if !links.contains(&link) {
+                links.push(link);
+            }
+            if links.len() == MAX_LINKS {
+                break;
+            }
+        }
+
+        links
+    }
+
+    // sanitised HTML of the formatted message, stored alongside the raw text
+    pub fn to_html(&self) -> String {
+        rich_text::to_html(&self.0)
+    }
+}
+
+impl AsRef<str> for MessageContent {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PageLimit(i64);
+
+impl PageLimit {
+    const DEFAULT: i64 = 50;
+    const MAX: i64 = 100;
+}
+
+impl TryFrom<Option<i64>> for PageLimit {
+    type Error = RequestError;
+
+    fn try_from(value: Option<i64>) -> Result<Self, Self::Error> {
+        match value.unwrap_or(Self::DEFAULT) {
+            limit @ 1..=Self::MAX => Ok(Self(limit)),
+            _ => Err(RequestError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                Self::MAX
+            ))),
+        }
+    }
+}
+
+impl From<PageLimit> for i64 {
+    fn from(value: PageLimit) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions() {
+        let content = MessageContent::try_from(
+            "@alice, ping @bob and @alice again; mail me at carol@example.com or @ nobody (@dave)"
+                .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(content.mentions(), vec!["alice", "bob", "dave"]);
+    }
+
+    #[test]
+    fn test_links() {
+        let content = MessageContent::try_from(
+            "see https://example.com/a. and (https://en.wikipedia.org/wiki/Rust_(language)), \
+                not ftp://example.com or http:// but <https://Example.com/a> again, \
+                then https://one.test https://two.test"
+                .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            content.links(),
+            vec![
+                "https://example.com/a",
+                "https://en.wikipedia.org/wiki/Rust_(language)",
+                "https://one.test/",
+            ]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::app::{models::attachments::MAX_FILES_PER_UPLOAD, request_error::RequestError};
+
+use super::domain;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CreateMessageRequest {
+    pub content: String,
+    // replies to a top-level message in the same room
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    // uploaded beforehand with `POST /rooms/{id}/attachments`
+    #[serde(default)]
+    pub attachment_ids: Vec<Uuid>,
+}
+
+pub struct ValidCreateMessageRequest {
+    pub content: domain::MessageContent,
+    pub parent_id: Option<Uuid>,
+    pub attachment_ids: Vec<Uuid>,
+}
+
+impl TryFrom<CreateMessageRequest> for ValidCreateMessageRequest {
+    type Error = RequestError;
+
+    fn try_from(value: CreateMessageRequest) -> Result<Self, Self::Error> {
+        let mut attachment_ids = value.attachment_ids;
+        attachment_ids.sort();
+        attachment_ids.dedup();
+        if attachment_ids.len() > MAX_FILES_PER_UPLOAD {
+            return Err(RequestError::BadRequest(format!(
+                "A message can have at most {MAX_FILES_PER_UPLOAD} attachments"
+            )));
+        }
+
+        // a message made of attachments only needs no text
+        let content = match attachment_ids.is_empty() {
+            true => value.content.try_into()?,
+            false => domain::MessageContent::caption(value.content)?,
+        };
+
+        Ok(Self {
+            content,
+            parent_id: value.parent_id,
+            attachment_ids,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct EditMessageRequest {
+    pub content: String,
+}
+
+pub struct ValidEditMessageRequest {
+    pub content: domain::MessageContent,
+}
+
+impl TryFrom<EditMessageRequest> for ValidEditMessageRequest {
+    type Error = RequestError;
+
+    fn try_from(value: EditMessageRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            content: value.content.try_into()?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MessageHistoryQuery {
+    // id of the oldest message the client already has
+    pub before: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+pub struct ValidMessageHistoryQuery {
+    pub before: Option<Uuid>,
+    pub limit: domain::PageLimit,
+}
+
+impl TryFrom<MessageHistoryQuery> for ValidMessageHistoryQuery {
+    type Error = RequestError;
+
+    fn try_from(value: MessageHistoryQuery) -> Result<Self, Self::Error> {
+        Ok(Self {
+            before: value.before,
+            limit: value.limit.try_into()?,
+        })
+    }
+}
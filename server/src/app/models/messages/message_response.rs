@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::app::models::{
+    attachments::AttachmentResponse, link_previews::LinkPreviewResponse, reactions::ReactionSummary,
+};
+
+#[derive(Debug, Clone, FromRow)]
+pub struct MessageEntity {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    pub user_id: Uuid,
+    pub content: String,
+    pub content_html: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub reply_count: i32,
+    pub last_reply_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MessageResponse {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    pub user_id: Uuid,
+    pub content: String,
+    // content rendered by the server, safe to insert as HTML
+    pub content_html: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub reply_count: i32,
+    pub last_reply_at: Option<DateTime<Utc>>,
+    // only filled in history responses, left out when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<ReactionSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentResponse>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub link_previews: Vec<LinkPreviewResponse>,
+}
+
+impl From<MessageEntity> for MessageResponse {
+    fn from(value: MessageEntity) -> Self {
+        // deleted messages stay in history as tombstones without their content
+        let (content, content_html) = match value.deleted_at {
+            Some(_) => (String::new(), String::new()),
+            None => (value.content, value.content_html),
+        };
+
+        Self {
+            id: value.id,
+            room_id: value.room_id,
+            user_id: value.user_id,
+            content,
+            content_html,
+            created_at: value.created_at,
+            edited_at: value.edited_at,
+            deleted_at: value.deleted_at,
+            deleted_by: value.deleted_by,
+            parent_id: value.parent_id,
+            reply_count: value.reply_count,
+            last_reply_at: value.last_reply_at,
+            reactions: Vec::new(),
+            attachments: Vec::new(),
+            link_previews: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ThreadResponse {
+    pub parent: MessageResponse,
+    // newest first
+    pub replies: Vec<MessageResponse>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct PinEntity {
+    pub message_id: Uuid,
+    pub room_id: Uuid,
+    pub pinned_by: Option<Uuid>,
+    pub pinned_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PinnedMessageResponse {
+    // the message as it is now, pins follow edits
+    pub message: MessageResponse,
+    pub pinned_by: Option<Uuid>,
+    pub pinned_at: DateTime<Utc>,
+}
+
+impl PinnedMessageResponse {
+    pub fn new(message: MessageEntity, pin: PinEntity) -> Self {
+        Self {
+            message: message.into(),
+            pinned_by: pin.pinned_by,
+            pinned_at: pin.pinned_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct MessageRevisionResponse {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    // content as it was before the edit
+    pub content: String,
+    pub edited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,6 @@
+pub mod domain;
+pub mod message_request;
+pub mod message_response;
+
+pub use message_request::*;
+pub use message_response::*;
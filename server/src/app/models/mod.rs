@@ -1,2 +1,12 @@
+pub mod attachments;
+pub mod events;
+pub mod health;
+pub mod link_previews;
+pub mod messages;
+pub mod presence;
 pub mod profiles;
+pub mod reactions;
+pub mod rooms;
+pub mod search;
 pub mod users;
+pub mod ws_tickets;
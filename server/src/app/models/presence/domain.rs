@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    Online,
+    Away,
+    // do not disturb, only ever set by the user
+    Dnd,
+    Offline,
+}
+
+impl PresenceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PresenceStatus::Online => "online",
+            PresenceStatus::Away => "away",
+            PresenceStatus::Dnd => "dnd",
+            PresenceStatus::Offline => "offline",
+        }
+    }
+
+    pub fn is_override(&self) -> bool {
+        matches!(self, PresenceStatus::Away | PresenceStatus::Dnd)
+    }
+}
+
+impl From<&str> for PresenceStatus {
+    fn from(value: &str) -> Self {
+        match value {
+            "online" => PresenceStatus::Online,
+            "away" => PresenceStatus::Away,
+            "dnd" => PresenceStatus::Dnd,
+            _ => PresenceStatus::Offline,
+        }
+    }
+}
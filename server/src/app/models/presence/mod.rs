@@ -0,0 +1,6 @@
+pub mod domain;
+pub mod presence_request;
+pub mod presence_response;
+
+pub use presence_request::*;
+pub use presence_response::*;
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::app::request_error::RequestError;
+
+use super::domain::PresenceStatus;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct SetPresenceRequest {
+    // "away" or "dnd", null goes back to the status derived from the sessions
+    pub status: Option<PresenceStatus>,
+}
+
+pub struct ValidSetPresenceRequest {
+    pub status: Option<PresenceStatus>,
+}
+
+impl TryFrom<SetPresenceRequest> for ValidSetPresenceRequest {
+    type Error = RequestError;
+
+    fn try_from(value: SetPresenceRequest) -> Result<Self, Self::Error> {
+        if value.status.is_some_and(|status| !status.is_override()) {
+            return Err(RequestError::BadRequest(
+                "Presence can only be set to away or dnd".into(),
+            ));
+        }
+
+        Ok(Self {
+            status: value.status,
+        })
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::domain::PresenceStatus;
+
+#[derive(Debug, FromRow)]
+pub struct PresenceEntity {
+    pub user_id: Uuid,
+    pub presence_override: Option<String>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
+impl PresenceEntity {
+    pub fn presence_override(&self) -> Option<PresenceStatus> {
+        self.presence_override.as_deref().map(PresenceStatus::from)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PresenceResponse {
+    pub user_id: Uuid,
+    pub status: PresenceStatus,
+    // when the last session of the user closed
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
@@ -1,4 +1,7 @@
-use crate::app::request_error::RequestError;
+use crate::app::{
+    media::avatars::{AVATAR_SIZES, DEFAULT_AVATAR_SIZE},
+    request_error::RequestError,
+};
 
 const MAX_USERNAME_LENGTH: usize = 256;
 const MIN_AGE: i32 = 12;
@@ -41,7 +44,7 @@ impl TryFrom<i32> for Age {
     type Error = RequestError;
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
-        if value < MIN_AGE || value > MAX_AGE {
+        if !(MIN_AGE..=MAX_AGE).contains(&value) {
             return Err(RequestError::BadRequest("Invalid age".into()));
         }
 
@@ -77,3 +80,34 @@ impl AsRef<str> for AboutMe {
         &self.0
     }
 }
+
+// one of the stored avatar sizes, with its edge in pixels
+#[derive(Debug, Clone, Copy)]
+pub struct AvatarSize(&'static str, u32);
+
+impl TryFrom<Option<String>> for AvatarSize {
+    type Error = RequestError;
+
+    fn try_from(value: Option<String>) -> Result<Self, Self::Error> {
+        let value = value.as_deref().unwrap_or(DEFAULT_AVATAR_SIZE);
+
+        AVATAR_SIZES
+            .into_iter()
+            .find(|(name, _)| *name == value)
+            .map(|(name, edge)| Self(name, edge))
+            .ok_or_else(|| {
+                let sizes = AVATAR_SIZES.map(|(name, _)| name).join(", ");
+                RequestError::BadRequest(format!("Avatar size must be one of {sizes}"))
+            })
+    }
+}
+
+impl AvatarSize {
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+
+    pub fn edge(&self) -> u32 {
+        self.1
+    }
+}
@@ -1,3 +1,6 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
 use crate::app::request_error::RequestError;
 
 use super::domain;
@@ -25,3 +28,23 @@ impl TryFrom<CreateProfileRequest> for ValidCreateProfileRequest {
         })
     }
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AvatarQuery {
+    // small, medium or large; medium when left out
+    pub size: Option<String>,
+}
+
+pub struct ValidAvatarQuery {
+    pub size: domain::AvatarSize,
+}
+
+impl TryFrom<AvatarQuery> for ValidAvatarQuery {
+    type Error = RequestError;
+
+    fn try_from(value: AvatarQuery) -> Result<Self, Self::Error> {
+        Ok(Self {
+            size: value.size.try_into()?,
+        })
+    }
+}
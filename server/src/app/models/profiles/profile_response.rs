@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::Serialize;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize)]
@@ -11,4 +12,43 @@ pub struct ProfileEntity {
     pub about_me: String,
     pub updated_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    pub avatar_key: Option<String>,
+    pub avatar_content_type: Option<String>,
+    pub avatar_updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProfileResponse {
+    pub user_id: Uuid,
+    pub username: String,
+    pub age: i32,
+    pub about_me: String,
+    // the uploaded avatar or a generated identicon, `size` can be appended
+    pub avatar_url: String,
+    pub updated_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ProfileEntity> for ProfileResponse {
+    fn from(value: ProfileEntity) -> Self {
+        // a new upload changes the URL so cached copies are not shown
+        let avatar_url = match value.avatar_updated_at {
+            Some(updated_at) => format!(
+                "/users/{}/avatar?v={}",
+                value.user_id,
+                updated_at.timestamp_millis()
+            ),
+            None => format!("/users/{}/avatar", value.user_id),
+        };
+
+        Self {
+            user_id: value.user_id,
+            username: value.username,
+            age: value.age,
+            about_me: value.about_me,
+            avatar_url,
+            updated_at: value.updated_at,
+            created_at: value.created_at,
+        }
+    }
 }
@@ -0,0 +1,116 @@
+use unicode_properties::emoji::{UnicodeEmoji, is_regional_indicator};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::app::request_error::RequestError;
+
+const MAX_SHORTCODE_LENGTH: usize = 32;
+// family and profession sequences are the longest ones, at around 35 bytes
+const MAX_EMOJI_BYTES: usize = 64;
+const COMBINING_KEYCAP: char = '\u{20e3}';
+
+// a single unicode emoji such as "👍🏽" or a custom emoji shortcode such as ":party_parrot:"
+#[derive(Debug, Clone)]
+pub struct ReactionEmoji(String);
+
+impl ReactionEmoji {
+    pub fn is_shortcode(&self) -> bool {
+        self.0.starts_with(':')
+    }
+}
+
+impl TryFrom<String> for ReactionEmoji {
+    type Error = RequestError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if is_shortcode(&value) || is_single_emoji(&value) {
+            return Ok(Self(value));
+        }
+
+        Err(RequestError::BadRequest(
+            "Reaction must be a single emoji or a custom emoji shortcode".into(),
+        ))
+    }
+}
+
+impl AsRef<str> for ReactionEmoji {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+fn is_shortcode(value: &str) -> bool {
+    let Some(name) = value
+        .strip_prefix(':')
+        .and_then(|value| value.strip_suffix(':'))
+    else {
+        return false;
+    };
+
+    (1..=MAX_SHORTCODE_LENGTH).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-'))
+}
+
+// letters such as "é" or "中" are single graphemes too, so one code point has to be a
+// pictograph, a flag letter or a keycap; zero-width joiners and variation selectors are
+// fine inside a sequence but not on their own
+fn is_single_emoji(value: &str) -> bool {
+    value.len() <= MAX_EMOJI_BYTES
+        && value.graphemes(true).count() == 1
+        && !value.chars().any(|c| c.is_whitespace() || c.is_control())
+        && !value.starts_with(is_invisible)
+        && value.chars().any(is_emoji_base)
+}
+
+// digits, '#' and '*' are emoji components that only count as part of a keycap
+fn is_emoji_base(c: char) -> bool {
+    (c.is_emoji_char() && !c.is_emoji_component())
+        || is_regional_indicator(c)
+        || c == COMBINING_KEYCAP
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{200b}'..='\u{200f}' | '\u{2060}'..='\u{2064}' | '\u{fe00}'..='\u{fe0f}' | '\u{feff}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reaction_emoji() {
+        let valid = ["👍", "👍🏽", "👨‍👩‍👧", "🇺🇦", "#️⃣", "❤️", ":party_parrot:", ":+1:"];
+        for emoji in valid {
+            assert!(
+                ReactionEmoji::try_from(emoji.to_string()).is_ok(),
+                "{emoji}"
+            );
+        }
+
+        let too_long = format!("👍{}", "\u{1f3fd}".repeat(20));
+        let invalid = [
+            "",
+            "a",
+            "+",
+            "#",
+            "é",
+            "中",
+            "\u{1f3fd}",
+            "👍👍",
+            "👍 ",
+            "::",
+            ":Party:",
+            ":no spaces:",
+            "\u{200b}",
+            "\u{fe0f}",
+            too_long.as_str(),
+        ];
+        for emoji in invalid {
+            assert!(
+                ReactionEmoji::try_from(emoji.to_string()).is_err(),
+                "{emoji}"
+            );
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod domain;
+pub mod reaction_response;
+
+pub use reaction_response::*;
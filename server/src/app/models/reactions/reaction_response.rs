@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ReactionResponse {
+    pub message_id: Uuid,
+    pub room_id: Uuid,
+    pub user_id: Uuid,
+    pub emoji: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// reactions of one message grouped by emoji, in the order they were first used
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReactionSummary {
+    pub emoji: String,
+    pub count: i64,
+    // whether the caller is one of the reacting users
+    pub reacted: bool,
+}
+
+#[derive(Debug, FromRow)]
+pub struct ReactionCountEntity {
+    pub message_id: Uuid,
+    pub emoji: String,
+    pub count: i64,
+    pub reacted: bool,
+}
+
+impl From<ReactionCountEntity> for ReactionSummary {
+    fn from(value: ReactionCountEntity) -> Self {
+        Self {
+            emoji: value.emoji,
+            count: value.count,
+            reacted: value.reacted,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::app::request_error::RequestError;
+
+const MAX_ROOM_NAME_LENGTH: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct RoomName(String);
+
+impl TryFrom<String> for RoomName {
+    type Error = RequestError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value = value.trim();
+
+        if value.is_empty() {
+            return Err(RequestError::BadRequest("Room name is empty".into()));
+        }
+        if value.chars().count() > MAX_ROOM_NAME_LENGTH {
+            return Err(RequestError::BadRequest("Room name is too long".into()));
+        }
+
+        Ok(Self(value.to_owned()))
+    }
+}
+
+impl AsRef<str> for RoomName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl RoomRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoomRole::Owner => "owner",
+            RoomRole::Admin => "admin",
+            RoomRole::Member => "member",
+        }
+    }
+
+    pub fn can_moderate(&self) -> bool {
+        matches!(self, RoomRole::Owner | RoomRole::Admin)
+    }
+}
+
+impl From<&str> for RoomRole {
+    fn from(value: &str) -> Self {
+        match value {
+            "owner" => RoomRole::Owner,
+            "admin" => RoomRole::Admin,
+            _ => RoomRole::Member,
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod domain;
+pub mod room_request;
+pub mod room_response;
+
+pub use room_request::*;
+pub use room_response::*;
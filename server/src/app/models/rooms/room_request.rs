@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::app::request_error::RequestError;
+
+use super::domain;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CreateRoomRequest {
+    pub name: String,
+    // only invited users can join a private room
+    #[serde(default)]
+    pub is_private: bool,
+}
+
+pub struct ValidCreateRoomRequest {
+    pub name: domain::RoomName,
+    pub is_private: bool,
+}
+
+impl TryFrom<CreateRoomRequest> for ValidCreateRoomRequest {
+    type Error = RequestError;
+
+    fn try_from(value: CreateRoomRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name.try_into()?,
+            is_private: value.is_private,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CreateInviteRequest {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct MarkReadRequest {
+    // newest message the caller has seen; older ones never move the marker back
+    pub message_id: Uuid,
+}
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::domain::RoomRole;
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct RoomResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub is_private: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct RoomInviteResponse {
+    pub room_id: Uuid,
+    pub user_id: Uuid,
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ReadReceiptResponse {
+    pub room_id: Uuid,
+    pub user_id: Uuid,
+    pub last_read_message_id: Option<Uuid>,
+    pub last_read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct UnreadCountResponse {
+    pub room_id: Uuid,
+    pub last_read_message_id: Option<Uuid>,
+    pub unread_count: i64,
+}
+
+#[derive(Debug, FromRow)]
+pub struct RoomMemberEntity {
+    pub room_id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+    pub joined_at: DateTime<Utc>,
+}
+
+impl RoomMemberEntity {
+    pub fn role(&self) -> RoomRole {
+        RoomRole::from(self.role.as_str())
+    }
+}
@@ -0,0 +1,30 @@
+use crate::app::request_error::RequestError;
+
+const MAX_SEARCH_LENGTH: usize = 256;
+
+// web search syntax: words, "quoted phrases", `or` and -exclusions
+#[derive(Debug, Clone)]
+pub struct SearchTerms(String);
+
+impl TryFrom<String> for SearchTerms {
+    type Error = RequestError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value = value.trim();
+
+        if value.is_empty() {
+            return Err(RequestError::BadRequest("Search query is empty".into()));
+        }
+        if value.chars().count() > MAX_SEARCH_LENGTH {
+            return Err(RequestError::BadRequest("Search query is too long".into()));
+        }
+
+        Ok(Self(value.to_owned()))
+    }
+}
+
+impl AsRef<str> for SearchTerms {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,6 @@
+pub mod domain;
+pub mod search_request;
+pub mod search_response;
+
+pub use search_request::*;
+pub use search_response::*;
@@ -0,0 +1,41 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::app::{models::messages::domain::PageLimit, request_error::RequestError};
+
+use super::domain;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SearchMessagesQuery {
+    pub q: String,
+    // only this room
+    pub room: Option<Uuid>,
+    // only messages by this user
+    pub from: Option<Uuid>,
+    // id of the oldest hit the client already has
+    pub before: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+pub struct ValidSearchMessagesQuery {
+    pub q: domain::SearchTerms,
+    pub room: Option<Uuid>,
+    pub from: Option<Uuid>,
+    pub before: Option<Uuid>,
+    pub limit: PageLimit,
+}
+
+impl TryFrom<SearchMessagesQuery> for ValidSearchMessagesQuery {
+    type Error = RequestError;
+
+    fn try_from(value: SearchMessagesQuery) -> Result<Self, Self::Error> {
+        Ok(Self {
+            q: value.q.try_into()?,
+            room: value.room,
+            from: value.from,
+            before: value.before,
+            limit: value.limit.try_into()?,
+        })
+    }
+}
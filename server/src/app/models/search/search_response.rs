@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::app::models::messages::{MessageEntity, MessageResponse};
+
+#[derive(Debug, FromRow)]
+pub struct SearchHitEntity {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    pub user_id: Uuid,
+    pub content: String,
+    pub content_html: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub reply_count: i32,
+    pub last_reply_at: Option<DateTime<Utc>>,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchHitResponse {
+    pub message: MessageResponse,
+    // HTML-escaped content around the matches, which are wrapped in <mark></mark>
+    pub snippet: String,
+}
+
+impl From<SearchHitEntity> for SearchHitResponse {
+    fn from(value: SearchHitEntity) -> Self {
+        let message = MessageEntity {
+            id: value.id,
+            room_id: value.room_id,
+            user_id: value.user_id,
+            content: value.content,
+            content_html: value.content_html,
+            created_at: value.created_at,
+            edited_at: value.edited_at,
+            deleted_at: value.deleted_at,
+            deleted_by: value.deleted_by,
+            parent_id: value.parent_id,
+            reply_count: value.reply_count,
+            last_reply_at: value.last_reply_at,
+        };
+
+        Self {
+            message: message.into(),
+            snippet: value.snippet,
+        }
+    }
+}
@@ -18,6 +18,18 @@ pub struct UserEntity {
     pub password: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub presence_override: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub roles: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub disabled_at: Option<DateTime<Utc>>,
 }
 
 impl From<UserEntity> for UserResponse {
@@ -0,0 +1,5 @@
+pub mod ws_ticket_request;
+pub mod ws_ticket_response;
+
+pub use ws_ticket_request::*;
+pub use ws_ticket_response::*;
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConnectQuery {
+    // from `POST /ws/tickets`
+    pub ticket: String,
+}
@@ -0,0 +1,10 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WsTicketResponse {
+    // passed as `/ws?ticket=`, once and before `expires_at`
+    pub ticket: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,454 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use actix_ws::{CloseCode, CloseReason};
+use bytestring::ByteString;
+use tokio::sync::mpsc::{
+    Receiver, Sender, channel,
+    error::{TryRecvError, TrySendError},
+};
+use uuid::Uuid;
+
+use crate::app::models::{events::ServerEvent, presence::domain::PresenceStatus};
+
+// frames a session may have queued before it counts as too slow to keep up
+const SESSION_QUEUE_SIZE: usize = 256;
+const OVERFLOW_REASON: &str = "Client is too slow to keep up";
+
+#[derive(Debug, Clone)]
+pub enum SessionCommand {
+    Send(ByteString),
+    Close(CloseReason),
+}
+
+struct SessionHandle {
+    user_id: Uuid,
+    tx: Sender<SessionCommand>,
+    overflowed: Arc<AtomicBool>,
+}
+
+impl SessionHandle {
+    fn send(&self, command: SessionCommand) {
+        if let Err(TrySendError::Full(_)) = self.tx.try_send(command) {
+            self.overflowed.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+// a full queue has no room left for a close frame, so the receiving side turns
+// whatever it reads next into a policy close
+pub struct SessionReceiver {
+    rx: Receiver<SessionCommand>,
+    overflowed: Arc<AtomicBool>,
+}
+
+impl SessionReceiver {
+    pub async fn recv(&mut self) -> Option<SessionCommand> {
+        let command = self.rx.recv().await?;
+        Some(self.check_overflow(command))
+    }
+
+    pub fn try_recv(&mut self) -> Result<SessionCommand, TryRecvError> {
+        let command = self.rx.try_recv()?;
+        Ok(self.check_overflow(command))
+    }
+
+    fn check_overflow(&self, command: SessionCommand) -> SessionCommand {
+        match self.overflowed.load(Ordering::Relaxed) {
+            true => close_command(CloseCode::Policy, OVERFLOW_REASON),
+            false => command,
+        }
+    }
+}
+
+#[derive(Default)]
+struct HubState {
+    sessions: HashMap<Uuid, SessionHandle>,
+    user_sessions: HashMap<Uuid, HashSet<Uuid>>,
+    // room id -> ids of the users in it that have at least one open session
+    room_users: HashMap<Uuid, HashSet<Uuid>>,
+    user_rooms: HashMap<Uuid, HashSet<Uuid>>,
+    // sessions whose client reported the user as inactive
+    idle_sessions: HashSet<Uuid>,
+    // manual statuses of connected users
+    overrides: HashMap<Uuid, PresenceStatus>,
+}
+
+// in-process registry of WebSocket sessions and the rooms their users belong to
+#[derive(Clone, Default)]
+pub struct ChatHub {
+    state: Arc<RwLock<HubState>>,
+    closing: Arc<AtomicBool>,
+}
+
+impl ChatHub {
+    pub fn register(&self, user_id: Uuid, room_ids: Vec<Uuid>) -> (Uuid, SessionReceiver) {
+        let (tx, rx) = channel(SESSION_QUEUE_SIZE);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        let session_id = Uuid::new_v4();
+        let session = SessionHandle {
+            user_id,
+            tx,
+            overflowed: overflowed.clone(),
+        };
+
+        let mut state = self.write();
+        state.sessions.insert(session_id, session);
+        state
+            .user_sessions
+            .entry(user_id)
+            .or_default()
+            .insert(session_id);
+
+        for room_id in room_ids {
+            state.room_users.entry(room_id).or_default().insert(user_id);
+            state.user_rooms.entry(user_id).or_default().insert(room_id);
+        }
+
+        (session_id, SessionReceiver { rx, overflowed })
+    }
+
+    pub fn unregister(&self, session_id: Uuid) {
+        let mut state = self.write();
+
+        let Some(session) = state.sessions.remove(&session_id) else {
+            return;
+        };
+        state.idle_sessions.remove(&session_id);
+
+        let user_id = session.user_id;
+        let has_sessions = state.user_sessions.get_mut(&user_id).is_some_and(|s| {
+            s.remove(&session_id);
+            !s.is_empty()
+        });
+        if has_sessions {
+            return;
+        }
+
+        state.user_sessions.remove(&user_id);
+        state.overrides.remove(&user_id);
+        for room_id in state.user_rooms.remove(&user_id).unwrap_or_default() {
+            remove_user_from_room(&mut state, room_id, user_id);
+        }
+    }
+
+    // called after a membership change so already connected sessions follow it
+    pub fn join(&self, user_id: Uuid, room_id: Uuid) {
+        let mut state = self.write();
+
+        if !state.user_sessions.contains_key(&user_id) {
+            return;
+        }
+
+        state.room_users.entry(room_id).or_default().insert(user_id);
+        state.user_rooms.entry(user_id).or_default().insert(room_id);
+    }
+
+    pub fn leave(&self, user_id: Uuid, room_id: Uuid) {
+        let mut state = self.write();
+
+        if let Some(rooms) = state.user_rooms.get_mut(&user_id) {
+            rooms.remove(&room_id);
+        }
+        remove_user_from_room(&mut state, room_id, user_id);
+    }
+
+    pub fn is_in_room(&self, user_id: Uuid, room_id: Uuid) -> bool {
+        self.read()
+            .room_users
+            .get(&room_id)
+            .is_some_and(|users| users.contains(&user_id))
+    }
+
+    pub fn broadcast(&self, room_id: Uuid, event: &ServerEvent) {
+        self.broadcast_except(room_id, None, event);
+    }
+
+    // for events about a user that the user's own sessions do not need, like typing
+    pub fn broadcast_to_others(&self, room_id: Uuid, user_id: Uuid, event: &ServerEvent) {
+        self.broadcast_except(room_id, Some(user_id), event);
+    }
+
+    fn broadcast_except(&self, room_id: Uuid, except: Option<Uuid>, event: &ServerEvent) {
+        let Some(text) = encode(event) else {
+            return;
+        };
+
+        let state = self.read();
+        let Some(user_ids) = state.room_users.get(&room_id) else {
+            return;
+        };
+
+        for user_id in user_ids.iter().filter(|id| Some(**id) != except) {
+            send_to_sessions(&state, *user_id, &text);
+        }
+    }
+
+    // online while any session is active, away once all of them are idle, unless the
+    // user picked a status themselves
+    pub fn presence(&self, user_id: Uuid) -> PresenceStatus {
+        let state = self.read();
+
+        let Some(session_ids) = state.user_sessions.get(&user_id) else {
+            return PresenceStatus::Offline;
+        };
+        if let Some(status) = state.overrides.get(&user_id) {
+            return *status;
+        }
+
+        match session_ids.is_subset(&state.idle_sessions) {
+            true => PresenceStatus::Away,
+            false => PresenceStatus::Online,
+        }
+    }
+
+    pub fn set_idle(&self, session_id: Uuid, idle: bool) {
+        let mut state = self.write();
+
+        match idle {
+            true => state.idle_sessions.insert(session_id),
+            false => state.idle_sessions.remove(&session_id),
+        };
+    }
+
+    // ignored for users without sessions, the stored override is loaded when they connect
+    pub fn set_override(&self, user_id: Uuid, status: Option<PresenceStatus>) {
+        let mut state = self.write();
+
+        if !state.user_sessions.contains_key(&user_id) {
+            return;
+        }
+        match status {
+            Some(status) => state.overrides.insert(user_id, status),
+            None => state.overrides.remove(&user_id),
+        };
+    }
+
+    // connected users sharing a room with the user, including the user
+    pub fn peers(&self, user_id: Uuid) -> HashSet<Uuid> {
+        let state = self.read();
+
+        let room_ids = state.user_rooms.get(&user_id).into_iter().flatten();
+        let mut peers = room_ids
+            .filter_map(|room_id| state.room_users.get(room_id))
+            .flatten()
+            .copied()
+            .collect::<HashSet<_>>();
+        peers.insert(user_id);
+
+        peers
+    }
+
+    pub fn send_to_user(&self, user_id: Uuid, event: &ServerEvent) {
+        if let Some(text) = encode(event) {
+            send_to_sessions(&self.read(), user_id, &text);
+        }
+    }
+
+    pub fn session_count(&self) -> usize {
+        self.read().sessions.len()
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing.load(Ordering::Relaxed)
+    }
+
+    // asks the sessions of one user to send a close frame, returns how many it asked
+    pub fn close_user(&self, user_id: Uuid, code: CloseCode, reason: &str) -> usize {
+        let state = self.read();
+        let Some(session_ids) = state.user_sessions.get(&user_id) else {
+            return 0;
+        };
+
+        for session_id in session_ids {
+            if let Some(session) = state.sessions.get(session_id) {
+                session.send(close_command(code, reason));
+            }
+        }
+
+        session_ids.len()
+    }
+
+    // asks every session to send a close frame; new sessions are refused from now on
+    pub fn close_all(&self, reason: &str) {
+        self.closing.store(true, Ordering::Relaxed);
+
+        for session in self.read().sessions.values() {
+            session.send(close_command(CloseCode::Restart, reason));
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HubState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HubState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn encode(event: &ServerEvent) -> Option<ByteString> {
+    match serde_json::to_string(event) {
+        Ok(text) => Some(text.into()),
+        Err(e) => {
+            tracing::error!("Failed to encode server event: {}", e);
+            None
+        }
+    }
+}
+
+fn close_command(code: CloseCode, reason: &str) -> SessionCommand {
+    SessionCommand::Close(CloseReason {
+        code,
+        description: Some(reason.into()),
+    })
+}
+
+fn send_to_sessions(state: &HubState, user_id: Uuid, text: &ByteString) {
+    let session_ids = state.user_sessions.get(&user_id).into_iter().flatten();
+
+    for session_id in session_ids {
+        if let Some(session) = state.sessions.get(session_id) {
+            session.send(SessionCommand::Send(text.clone()));
+        }
+    }
+}
+
+fn remove_user_from_room(state: &mut HubState, room_id: Uuid, user_id: Uuid) {
+    let is_empty = state.room_users.get_mut(&room_id).is_some_and(|users| {
+        users.remove(&user_id);
+        users.is_empty()
+    });
+
+    if is_empty {
+        state.room_users.remove(&room_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received(rx: &mut SessionReceiver) -> Vec<String> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|command| match command {
+                SessionCommand::Send(text) => text.to_string(),
+                SessionCommand::Close(reason) => format!(
+                    "close: {:?} {}",
+                    reason.code,
+                    reason.description.unwrap_or_default()
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_broadcast_reaches_room_members_only() {
+        let hub = ChatHub::default();
+        let (room, other_room) = (Uuid::new_v4(), Uuid::new_v4());
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let (_, mut alice_phone) = hub.register(alice, vec![room]);
+        let (_, mut alice_laptop) = hub.register(alice, vec![room]);
+        let (bob_session, mut bob_rx) = hub.register(bob, vec![other_room]);
+
+        let event = ServerEvent::Error {
+            message: "hello".into(),
+        };
+        hub.broadcast(room, &event);
+
+        let exp = r#"{"type":"error","payload":{"message":"hello"}}"#;
+        assert_eq!(received(&mut alice_phone), vec![exp]);
+        assert_eq!(received(&mut alice_laptop), vec![exp]);
+        assert!(received(&mut bob_rx).is_empty());
+
+        hub.join(bob, room);
+        hub.broadcast(room, &event);
+        assert_eq!(received(&mut bob_rx), vec![exp]);
+
+        hub.leave(bob, room);
+        hub.broadcast(room, &event);
+        assert!(received(&mut bob_rx).is_empty());
+
+        hub.unregister(bob_session);
+        assert_eq!(hub.session_count(), 2);
+        assert_eq!(received(&mut alice_phone).len(), 2);
+        assert_eq!(received(&mut alice_laptop).len(), 2);
+
+        assert_eq!(
+            hub.close_user(alice, CloseCode::Policy, "User is disabled"),
+            2
+        );
+        for rx in [&mut alice_phone, &mut alice_laptop] {
+            assert_eq!(received(rx), vec!["close: Policy User is disabled"]);
+        }
+        assert_eq!(
+            hub.close_user(bob, CloseCode::Policy, "User is disabled"),
+            0
+        );
+
+        hub.close_all("server restarting");
+        assert!(hub.is_closing());
+        assert_eq!(
+            received(&mut alice_phone),
+            vec!["close: Restart server restarting"]
+        );
+    }
+
+    #[test]
+    fn test_slow_session_is_closed() {
+        let hub = ChatHub::default();
+        let (room, alice) = (Uuid::new_v4(), Uuid::new_v4());
+        let (_, mut rx) = hub.register(alice, vec![room]);
+
+        let event = ServerEvent::Error {
+            message: "hello".into(),
+        };
+        for _ in 0..SESSION_QUEUE_SIZE {
+            hub.broadcast(room, &event);
+        }
+        assert_eq!(received(&mut rx).len(), SESSION_QUEUE_SIZE);
+
+        for _ in 0..=SESSION_QUEUE_SIZE {
+            hub.broadcast(room, &event);
+        }
+        let exp = "close: Policy Client is too slow to keep up";
+        assert_eq!(received(&mut rx).first().map(String::as_str), Some(exp));
+    }
+
+    #[test]
+    fn test_presence_follows_sessions() {
+        let hub = ChatHub::default();
+        let room = Uuid::new_v4();
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(hub.presence(alice), PresenceStatus::Offline);
+
+        let (phone, _phone_rx) = hub.register(alice, vec![room]);
+        let (laptop, _laptop_rx) = hub.register(alice, vec![room]);
+        let (_, _bob_rx) = hub.register(bob, vec![room]);
+        let (_, _carol_rx) = hub.register(carol, vec![Uuid::new_v4()]);
+        assert_eq!(hub.presence(alice), PresenceStatus::Online);
+        assert_eq!(hub.peers(alice), HashSet::from([alice, bob]));
+
+        hub.set_idle(phone, true);
+        assert_eq!(hub.presence(alice), PresenceStatus::Online);
+        hub.set_idle(laptop, true);
+        assert_eq!(hub.presence(alice), PresenceStatus::Away);
+
+        hub.set_override(alice, Some(PresenceStatus::Dnd));
+        assert_eq!(hub.presence(alice), PresenceStatus::Dnd);
+
+        hub.unregister(phone);
+        hub.unregister(laptop);
+        assert_eq!(hub.presence(alice), PresenceStatus::Offline);
+
+        // overrides of disconnected users come from the database on the next connect
+        let (_, _rx) = hub.register(alice, vec![room]);
+        assert_eq!(hub.presence(alice), PresenceStatus::Online);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod hub;
+pub mod session;
+pub mod typing;
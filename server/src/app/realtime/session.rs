@@ -0,0 +1,254 @@
+use std::time::{Duration, Instant};
+
+use actix_ws::{AggregatedMessage, AggregatedMessageStream, CloseCode, CloseReason, Session};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        models::{
+            events::{ClientEvent, ServerEvent},
+            messages::{CreateMessageRequest, EditMessageRequest},
+            presence::PresenceEntity,
+        },
+        realtime::{
+            hub::SessionCommand,
+            typing::{TYPING_TTL, TypingState},
+        },
+        repositories::{room_repository, user_repository},
+        request_error::{RequestError, RequestResult},
+        services::{message_service, presence_service, reaction_service, room_service},
+    },
+    core::app_data::AppData,
+};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+pub async fn run(
+    mut session: Session,
+    mut stream: AggregatedMessageStream,
+    user_id: Uuid,
+    app_data: AppData,
+) {
+    let (room_ids, presence) = match load_user(user_id, &app_data.pool).await {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            tracing::error!("Failed to load user for WebSocket session: {}", e);
+            let _ = session.close(Some(CloseCode::Error.into())).await;
+            return;
+        }
+    };
+
+    let before = app_data.hub.presence(user_id);
+    let (session_id, mut commands) = app_data.hub.register(user_id, room_ids);
+    presence_service::connected(user_id, before, presence.presence_override(), &app_data.hub);
+    app_data.metrics.ws_connections.inc();
+    tracing::info!(%session_id, %user_id, "WebSocket session opened");
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_heartbeat = Instant::now();
+    let mut typing = TypingState::default();
+
+    let close_reason = loop {
+        tokio::select! {
+            message = stream.recv() => {
+                last_heartbeat = Instant::now();
+
+                match message {
+                    Some(Ok(AggregatedMessage::Text(text))) => {
+                        if let Err(e) = handle_client_event(&text, user_id, session_id, &mut typing, &app_data).await {
+                            let event = ServerEvent::Error { message: e.to_string() };
+                            if send(&mut session, &event).await.is_err() {
+                                break None;
+                            }
+                        }
+                    }
+                    Some(Ok(AggregatedMessage::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break None;
+                        }
+                    }
+                    Some(Ok(AggregatedMessage::Pong(_))) => {}
+                    Some(Ok(AggregatedMessage::Binary(_))) => {
+                        break Some(CloseReason {
+                            code: CloseCode::Unsupported,
+                            description: Some("binary frames are not supported".into()),
+                        });
+                    }
+                    Some(Ok(AggregatedMessage::Close(reason))) => break reason,
+                    Some(Err(e)) => {
+                        tracing::warn!("WebSocket protocol error: {}", e);
+                        break Some(CloseCode::Protocol.into());
+                    }
+                    None => break None,
+                }
+            }
+            command = commands.recv() => match command {
+                Some(SessionCommand::Send(text)) => {
+                    if session.text(text).await.is_err() {
+                        break None;
+                    }
+                }
+                Some(SessionCommand::Close(reason)) => break Some(reason),
+                None => break None,
+            },
+            _ = heartbeat.tick() => {
+                if last_heartbeat.elapsed() > CLIENT_TIMEOUT {
+                    tracing::info!(%session_id, "WebSocket client timed out");
+                    break None;
+                }
+                if session.ping(b"").await.is_err() {
+                    break None;
+                }
+            }
+        }
+    };
+
+    presence_service::disconnected(user_id, session_id, &app_data.pool, &app_data.hub).await;
+    for room_id in typing.stop_all(Instant::now()) {
+        let event = ServerEvent::TypingStopped { room_id, user_id };
+        app_data.hub.broadcast_to_others(room_id, user_id, &event);
+    }
+    app_data.metrics.ws_connections.dec();
+    tracing::info!(%session_id, %user_id, "WebSocket session closed");
+
+    let _ = session.close(close_reason).await;
+}
+
+async fn handle_client_event(
+    text: &str,
+    user_id: Uuid,
+    session_id: Uuid,
+    typing: &mut TypingState,
+    app_data: &AppData,
+) -> RequestResult<()> {
+    let event = serde_json::from_str::<ClientEvent>(text)
+        .map_err(|e| RequestError::BadRequest(e.to_string()))?;
+
+    match event {
+        ClientEvent::SendMessage {
+            room_id,
+            content,
+            parent_id,
+            attachment_ids,
+        } => {
+            let message = CreateMessageRequest {
+                content,
+                parent_id,
+                attachment_ids,
+            }
+            .try_into()?;
+            message_service::send_message(
+                user_id,
+                room_id,
+                message,
+                &app_data.pool,
+                &app_data.hub,
+                &app_data.link_jobs,
+            )
+            .await?;
+            app_data.metrics.messages_sent.inc();
+            typing.message_sent(room_id);
+        }
+        ClientEvent::EditMessage {
+            message_id,
+            content,
+        } => {
+            let message = EditMessageRequest { content }.try_into()?;
+            message_service::edit_message(
+                user_id,
+                message_id,
+                message,
+                &app_data.pool,
+                &app_data.hub,
+                &app_data.link_jobs,
+            )
+            .await?;
+        }
+        ClientEvent::DeleteMessage { message_id } => {
+            message_service::delete_message(user_id, message_id, &app_data.pool, &app_data.hub)
+                .await?;
+        }
+        ClientEvent::AddReaction { message_id, emoji } => {
+            let emoji = emoji.try_into()?;
+            reaction_service::add_reaction(
+                user_id,
+                message_id,
+                emoji,
+                &app_data.pool,
+                &app_data.hub,
+            )
+            .await?;
+        }
+        ClientEvent::Typing { room_id } => {
+            ensure_in_room(user_id, room_id, app_data)?;
+            if typing.start(room_id, Instant::now()) {
+                let event = ServerEvent::Typing {
+                    room_id,
+                    user_id,
+                    expires_in_ms: TYPING_TTL.as_millis() as u64,
+                };
+                app_data.hub.broadcast_to_others(room_id, user_id, &event);
+            }
+        }
+        ClientEvent::StopTyping { room_id } => {
+            ensure_in_room(user_id, room_id, app_data)?;
+            if typing.stop(room_id, Instant::now()) {
+                let event = ServerEvent::TypingStopped { room_id, user_id };
+                app_data.hub.broadcast_to_others(room_id, user_id, &event);
+            }
+        }
+        ClientEvent::SetAway { away } => {
+            presence_service::set_idle(user_id, session_id, away, &app_data.hub);
+        }
+        ClientEvent::MarkRead {
+            room_id,
+            message_id,
+        } => {
+            room_service::mark_read(user_id, room_id, message_id, &app_data.pool, &app_data.hub)
+                .await?;
+        }
+        ClientEvent::RemoveReaction { message_id, emoji } => {
+            let emoji = emoji.try_into()?;
+            reaction_service::remove_reaction(
+                user_id,
+                message_id,
+                emoji,
+                &app_data.pool,
+                &app_data.hub,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn load_user(user_id: Uuid, pool: &PgPool) -> RequestResult<(Vec<Uuid>, PresenceEntity)> {
+    let room_ids = room_repository::room_ids_for_user(user_id, pool).await?;
+    let presence = user_repository::get_presence(user_id, pool).await?;
+
+    Ok((room_ids, presence))
+}
+
+// typing events are checked against the hub's view of the rooms instead of Postgres
+fn ensure_in_room(user_id: Uuid, room_id: Uuid, app_data: &AppData) -> RequestResult<()> {
+    if !app_data.hub.is_in_room(user_id, room_id) {
+        return Err(RequestError::Forbidden(
+            "You are not a member of this room".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn send(session: &mut Session, event: &ServerEvent) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(event) {
+        Ok(text) => session.text(text).await,
+        Err(e) => {
+            tracing::error!("Failed to encode server event: {}", e);
+            Ok(())
+        }
+    }
+}
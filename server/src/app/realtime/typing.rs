@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+// clients drop the indicator after this unless the user keeps typing
+pub const TYPING_TTL: Duration = Duration::from_secs(5);
+// repeated typing events of one connection for a room are dropped within this window,
+// it is shorter than the ttl so a user who keeps typing never flickers
+const TYPING_THROTTLE: Duration = Duration::from_secs(3);
+
+// per-connection typing state, kept in memory only
+#[derive(Debug, Default)]
+pub struct TypingState {
+    // rooms that show the indicator and since when
+    showing: HashMap<Uuid, Instant>,
+    // when each room last heard about it, kept after a stop so that alternating
+    // start and stop cannot get around the throttle
+    last_started: HashMap<Uuid, Instant>,
+}
+
+impl TypingState {
+    // true when the room should hear about it
+    pub fn start(&mut self, room_id: Uuid, now: Instant) -> bool {
+        self.last_started
+            .retain(|_, at| now.duration_since(*at) < TYPING_THROTTLE);
+        if self.last_started.contains_key(&room_id) {
+            return false;
+        }
+
+        self.last_started.insert(room_id, now);
+        self.showing.insert(room_id, now);
+        true
+    }
+
+    // true when the indicator has not expired on its own yet
+    pub fn stop(&mut self, room_id: Uuid, now: Instant) -> bool {
+        self.showing
+            .remove(&room_id)
+            .is_some_and(|at| now.duration_since(at) < TYPING_TTL)
+    }
+
+    // clients hide the indicator once the message arrives, so the next keystroke
+    // should be announced right away
+    pub fn message_sent(&mut self, room_id: Uuid) {
+        self.showing.remove(&room_id);
+        self.last_started.remove(&room_id);
+    }
+
+    // rooms that still show the indicator, e.g. when the connection goes away
+    pub fn stop_all(&mut self, now: Instant) -> Vec<Uuid> {
+        self.last_started.clear();
+        self.showing
+            .drain()
+            .filter(|(_, at)| now.duration_since(*at) < TYPING_TTL)
+            .map(|(room_id, _)| room_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typing_is_throttled_and_expires() {
+        let mut typing = TypingState::default();
+        let (room, other_room) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = Instant::now();
+
+        assert!(typing.start(room, now));
+        assert!(!typing.start(room, now + Duration::from_secs(1)));
+        assert!(typing.start(other_room, now + Duration::from_secs(1)));
+        assert!(typing.start(room, now + TYPING_THROTTLE));
+
+        assert!(typing.stop(room, now + TYPING_THROTTLE + Duration::from_secs(1)));
+        assert!(!typing.stop(room, now + TYPING_THROTTLE + Duration::from_secs(1)));
+
+        // the indicator of other_room has already expired on the clients
+        assert!(typing.stop_all(now + TYPING_TTL * 2).is_empty());
+    }
+
+    #[test]
+    fn test_stop_does_not_reset_the_throttle() {
+        let mut typing = TypingState::default();
+        let room = Uuid::new_v4();
+        let now = Instant::now();
+
+        assert!(typing.start(room, now));
+        assert!(typing.stop(room, now));
+        for i in 1..10 {
+            let at = now + Duration::from_millis(100 * i);
+            assert!(!typing.start(room, at), "start {i} should be throttled");
+            assert!(!typing.stop(room, at), "stop {i} has nothing to stop");
+        }
+        assert!(typing.start(room, now + TYPING_THROTTLE));
+
+        // a sent message hides the indicator on the clients, so typing again is news
+        typing.message_sent(room);
+        assert!(typing.start(room, now + TYPING_THROTTLE));
+    }
+}
@@ -0,0 +1,239 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::{
+    media::images::Thumbnail,
+    models::attachments::{AttachmentEntity, ThumbnailEntity, ValidUploadedFile},
+    request_error::RequestResult,
+};
+
+#[tracing::instrument(name = "attachment_repository::create", skip_all, fields(db.system = "postgresql"))]
+pub async fn create<'c, E>(
+    room_id: Uuid,
+    uploaded_by: Uuid,
+    file: &ValidUploadedFile,
+    storage_key: &str,
+    exec: E,
+) -> RequestResult<AttachmentEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        AttachmentEntity,
+        "INSERT INTO attachments (room_id, uploaded_by, filename, content_type, size_bytes, storage_key) 
+            VALUES ($1, $2, $3, $4, $5, $6) 
+            RETURNING id, room_id, message_id, uploaded_by, filename, content_type, size_bytes, storage_key, created_at, 
+                width, height, blurhash, processed_at",
+        room_id,
+        uploaded_by,
+        file.filename.as_ref(),
+        file.content_type.as_ref(),
+        file.data.len() as i64,
+        storage_key
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "attachment_repository::get", skip_all, fields(db.system = "postgresql"))]
+pub async fn get<'c, E>(id: Uuid, exec: E) -> RequestResult<AttachmentEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        AttachmentEntity,
+        "SELECT id, room_id, message_id, uploaded_by, filename, content_type, size_bytes, storage_key, created_at, 
+                width, height, blurhash, processed_at 
+            FROM attachments WHERE id = $1",
+        id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// only claims the uploader's own attachments of the room that are not sent yet,
+// the caller compares the count with the ids it asked for
+#[tracing::instrument(name = "attachment_repository::link", skip_all, fields(db.system = "postgresql"))]
+pub async fn link<'c, E>(
+    message_id: Uuid,
+    room_id: Uuid,
+    uploaded_by: Uuid,
+    ids: &[Uuid],
+    exec: E,
+) -> RequestResult<Vec<AttachmentEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        AttachmentEntity,
+        "UPDATE attachments SET message_id = $1 
+            WHERE id = ANY($4) AND room_id = $2 AND uploaded_by = $3 AND message_id IS NULL 
+            RETURNING id, room_id, message_id, uploaded_by, filename, content_type, size_bytes, storage_key, created_at, 
+                width, height, blurhash, processed_at",
+        message_id,
+        room_id,
+        uploaded_by,
+        ids
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// attachments of deleted messages are left out
+#[tracing::instrument(name = "attachment_repository::list_for_messages", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_for_messages<'c, E>(
+    message_ids: &[Uuid],
+    exec: E,
+) -> RequestResult<Vec<AttachmentEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        AttachmentEntity,
+        "SELECT a.id, a.room_id, a.message_id, a.uploaded_by, a.filename, a.content_type, a.size_bytes, a.storage_key, a.created_at, 
+                a.width, a.height, a.blurhash, a.processed_at 
+            FROM attachments a 
+            JOIN messages m ON m.id = a.message_id 
+            WHERE a.message_id = ANY($1) AND m.deleted_at IS NULL 
+            ORDER BY a.created_at, a.id",
+        message_ids
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// images the worker has not been through yet, oldest first
+#[tracing::instrument(name = "attachment_repository::list_unprocessed", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_unprocessed<'c, E>(limit: i64, exec: E) -> RequestResult<Vec<AttachmentEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        AttachmentEntity,
+        "SELECT id, room_id, message_id, uploaded_by, filename, content_type, size_bytes, storage_key, created_at, 
+                width, height, blurhash, processed_at 
+            FROM attachments 
+            WHERE processed_at IS NULL AND content_type LIKE 'image/%' 
+            ORDER BY created_at 
+            LIMIT $1",
+        limit
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// dimensions are None when the image could not be decoded
+#[tracing::instrument(name = "attachment_repository::mark_processed", skip_all, fields(db.system = "postgresql"))]
+pub async fn mark_processed<'c, E>(
+    id: Uuid,
+    dimensions: Option<(u32, u32)>,
+    blurhash: Option<&str>,
+    size_bytes: i64,
+    exec: E,
+) -> RequestResult<AttachmentEntity>
+where
+    E: PgExecutor<'c>,
+{
+    let (width, height) =
+        dimensions.map_or((None, None), |(w, h)| (Some(w as i32), Some(h as i32)));
+
+    sqlx::query_as!(
+        AttachmentEntity,
+        "UPDATE attachments 
+            SET width = $2, height = $3, blurhash = $4, size_bytes = $5, processed_at = now() 
+            WHERE id = $1 
+            RETURNING id, room_id, message_id, uploaded_by, filename, content_type, size_bytes, storage_key, created_at, 
+                width, height, blurhash, processed_at",
+        id,
+        width,
+        height,
+        blurhash,
+        size_bytes
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// replaces a thumbnail of the same size left by an interrupted run
+#[tracing::instrument(name = "attachment_repository::create_thumbnail", skip_all, fields(db.system = "postgresql"))]
+pub async fn create_thumbnail<'c, E>(
+    attachment_id: Uuid,
+    thumbnail: &Thumbnail,
+    storage_key: &str,
+    exec: E,
+) -> RequestResult<ThumbnailEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ThumbnailEntity,
+        "INSERT INTO attachment_thumbnails 
+                (attachment_id, size, width, height, content_type, size_bytes, storage_key) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7) 
+            ON CONFLICT (attachment_id, size) DO UPDATE 
+                SET width = EXCLUDED.width, height = EXCLUDED.height, 
+                    content_type = EXCLUDED.content_type, size_bytes = EXCLUDED.size_bytes, 
+                    storage_key = EXCLUDED.storage_key 
+            RETURNING attachment_id, size, width, height, content_type, size_bytes, storage_key",
+        attachment_id,
+        thumbnail.size,
+        thumbnail.width as i32,
+        thumbnail.height as i32,
+        thumbnail.content_type,
+        thumbnail.data.len() as i64,
+        storage_key
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "attachment_repository::get_thumbnail", skip_all, fields(db.system = "postgresql"))]
+pub async fn get_thumbnail<'c, E>(
+    attachment_id: Uuid,
+    size: &str,
+    exec: E,
+) -> RequestResult<Option<ThumbnailEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ThumbnailEntity,
+        "SELECT attachment_id, size, width, height, content_type, size_bytes, storage_key 
+            FROM attachment_thumbnails 
+            WHERE attachment_id = $1 AND size = $2",
+        attachment_id,
+        size
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+// smallest first
+#[tracing::instrument(name = "attachment_repository::list_thumbnails", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_thumbnails<'c, E>(
+    attachment_ids: &[Uuid],
+    exec: E,
+) -> RequestResult<Vec<ThumbnailEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ThumbnailEntity,
+        "SELECT attachment_id, size, width, height, content_type, size_bytes, storage_key 
+            FROM attachment_thumbnails 
+            WHERE attachment_id = ANY($1) 
+            ORDER BY attachment_id, width * height",
+        attachment_ids
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
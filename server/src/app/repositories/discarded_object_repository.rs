@@ -0,0 +1,36 @@
+use sqlx::PgExecutor;
+
+use crate::app::request_error::RequestResult;
+
+// oldest first
+#[tracing::instrument(name = "discarded_object_repository::list", skip_all, fields(db.system = "postgresql"))]
+pub async fn list<'c, E>(limit: i64, exec: E) -> RequestResult<Vec<String>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "SELECT storage_key FROM discarded_objects 
+            ORDER BY created_at 
+            LIMIT $1",
+        limit
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "discarded_object_repository::forget", skip_all, fields(db.system = "postgresql"))]
+pub async fn forget<'c, E>(storage_keys: &[String], exec: E) -> RequestResult<u64>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "DELETE FROM discarded_objects 
+            WHERE storage_key = ANY($1)",
+        storage_keys
+    )
+    .execute(exec)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(From::from)
+}
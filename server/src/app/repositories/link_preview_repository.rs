@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::{
+    app::{models::link_previews::MessageLinkPreviewEntity, request_error::RequestResult},
+    core::unfurl::LinkMetadata,
+};
+
+// queues the URLs that were never fetched or were fetched before `stale_before`,
+// returns how many were queued
+#[tracing::instrument(name = "link_preview_repository::enqueue", skip_all, fields(db.system = "postgresql"))]
+pub async fn enqueue<'c, E>(
+    urls: &[String],
+    stale_before: DateTime<Utc>,
+    exec: E,
+) -> RequestResult<u64>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "INSERT INTO link_previews (url) 
+            SELECT unnest($1::TEXT[]) 
+            ON CONFLICT (url) DO UPDATE SET fetched_at = NULL 
+                WHERE link_previews.fetched_at < $2",
+        urls,
+        stale_before
+    )
+    .execute(exec)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(From::from)
+}
+
+// links the message to its URLs, in the order they appear in it
+#[tracing::instrument(name = "link_preview_repository::link", skip_all, fields(db.system = "postgresql"))]
+pub async fn link<'c, E>(message_id: Uuid, urls: &[String], exec: E) -> RequestResult<u64>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "INSERT INTO message_links (message_id, url, position) 
+            SELECT $1, l.url, l.position 
+                FROM unnest($2::TEXT[]) WITH ORDINALITY AS l(url, position) 
+            ON CONFLICT (message_id, url) DO UPDATE SET position = EXCLUDED.position",
+        message_id,
+        urls
+    )
+    .execute(exec)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(From::from)
+}
+
+// drops links that are no longer in the message after an edit
+#[tracing::instrument(name = "link_preview_repository::retain", skip_all, fields(db.system = "postgresql"))]
+pub async fn retain<'c, E>(message_id: Uuid, urls: &[String], exec: E) -> RequestResult<u64>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "DELETE FROM message_links 
+            WHERE message_id = $1 AND NOT url = ANY($2)",
+        message_id,
+        urls
+    )
+    .execute(exec)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(From::from)
+}
+
+// oldest first
+#[tracing::instrument(name = "link_preview_repository::list_pending", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_pending<'c, E>(limit: i64, exec: E) -> RequestResult<Vec<String>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "SELECT url FROM link_previews 
+            WHERE fetched_at IS NULL 
+            ORDER BY created_at 
+            LIMIT $1",
+        limit
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// stores the result of a fetch, an empty `metadata` clears the previous one
+#[tracing::instrument(name = "link_preview_repository::save", skip_all, fields(db.system = "postgresql"))]
+pub async fn save<'c, E>(url: &str, metadata: &LinkMetadata, exec: E) -> RequestResult<u64>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "UPDATE link_previews 
+            SET title = $2, description = $3, image_url = $4, site_name = $5, fetched_at = now() 
+            WHERE url = $1",
+        url,
+        metadata.title,
+        metadata.description,
+        metadata.image_url,
+        metadata.site_name
+    )
+    .execute(exec)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(From::from)
+}
+
+// previews with something to show, a stale one is kept until it is fetched again
+#[tracing::instrument(name = "link_preview_repository::list_for_messages", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_for_messages<'c, E>(
+    message_ids: &[Uuid],
+    exec: E,
+) -> RequestResult<Vec<MessageLinkPreviewEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageLinkPreviewEntity,
+        "SELECT ml.message_id, lp.url, lp.title, lp.description, lp.image_url, lp.site_name 
+            FROM message_links ml 
+            JOIN link_previews lp ON lp.url = ml.url 
+            WHERE ml.message_id = ANY($1) 
+                AND (lp.title IS NOT NULL OR lp.description IS NOT NULL) 
+            ORDER BY ml.message_id, ml.position",
+        message_ids
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// messages that link the URL and were sent or edited since `since`, deleted ones left out
+#[tracing::instrument(name = "link_preview_repository::list_linking_messages", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_linking_messages<'c, E>(
+    url: &str,
+    since: DateTime<Utc>,
+    exec: E,
+) -> RequestResult<Vec<Uuid>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "SELECT m.id 
+            FROM message_links ml 
+            JOIN messages m ON m.id = ml.message_id 
+            WHERE ml.url = $1 AND m.deleted_at IS NULL 
+                AND COALESCE(m.edited_at, m.created_at) >= $2",
+        url,
+        since
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
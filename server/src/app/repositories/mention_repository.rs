@@ -0,0 +1,83 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::{models::messages::MessageEntity, request_error::RequestResult};
+
+// stores mentions of the given usernames that belong to members of the message's
+// room other than its author and returns the ids of the users that were newly mentioned
+#[tracing::instrument(name = "mention_repository::create", skip_all, fields(db.system = "postgresql"))]
+pub async fn create<'c, E>(
+    message_id: Uuid,
+    usernames: &[String],
+    exec: E,
+) -> RequestResult<Vec<Uuid>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "INSERT INTO message_mentions (message_id, user_id) 
+            SELECT m.id, p.user_id 
+                FROM messages m 
+                JOIN room_members rm ON rm.room_id = m.room_id 
+                JOIN profiles p ON p.user_id = rm.user_id 
+                WHERE m.id = $1 AND p.username = ANY($2) AND p.user_id <> m.user_id 
+            ON CONFLICT DO NOTHING 
+            RETURNING user_id",
+        message_id,
+        usernames
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// drops mentions whose username is no longer in the message after an edit
+#[tracing::instrument(name = "mention_repository::retain", skip_all, fields(db.system = "postgresql"))]
+pub async fn retain<'c, E>(message_id: Uuid, usernames: &[String], exec: E) -> RequestResult<u64>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "DELETE FROM message_mentions mm 
+            USING profiles p 
+            WHERE mm.message_id = $1 AND p.user_id = mm.user_id AND NOT p.username = ANY($2)",
+        message_id,
+        usernames
+    )
+    .execute(exec)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(From::from)
+}
+
+// messages mentioning the user in rooms they are still in, newest first
+#[tracing::instrument(name = "mention_repository::list_for_user", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_for_user<'c, E>(
+    user_id: Uuid,
+    before: Option<Uuid>,
+    limit: i64,
+    exec: E,
+) -> RequestResult<Vec<MessageEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "SELECT m.id, m.room_id, m.user_id, m.content, m.content_html, m.created_at, m.edited_at, m.deleted_at, 
+                m.deleted_by, m.parent_id, m.reply_count, m.last_reply_at 
+            FROM message_mentions mm 
+            JOIN messages m ON m.id = mm.message_id 
+            JOIN room_members rm ON rm.room_id = m.room_id AND rm.user_id = mm.user_id 
+            WHERE mm.user_id = $1 AND m.deleted_at IS NULL 
+                AND ($2::uuid IS NULL 
+                    OR (m.created_at, m.id) < (SELECT created_at, id FROM messages WHERE id = $2)) 
+            ORDER BY m.created_at DESC, m.id DESC 
+            LIMIT $3",
+        user_id,
+        before,
+        limit
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
@@ -0,0 +1,532 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::{
+    models::{
+        messages::{MessageEntity, MessageRevisionResponse},
+        search::{SearchHitEntity, ValidSearchMessagesQuery},
+    },
+    request_error::RequestResult,
+};
+
+#[tracing::instrument(name = "message_repository::create", skip_all, fields(db.system = "postgresql"))]
+pub async fn create<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    content: &str,
+    content_html: &str,
+    parent_id: Option<Uuid>,
+    exec: E,
+) -> RequestResult<MessageEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "INSERT INTO messages (room_id, user_id, content, content_html, parent_id) 
+            VALUES ($1, $2, $3, $4, $5) 
+            RETURNING id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at",
+        room_id,
+        user_id,
+        content,
+        content_html,
+        parent_id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::get", skip_all, fields(db.system = "postgresql"))]
+pub async fn get<'c, E>(id: Uuid, exec: E) -> RequestResult<MessageEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "SELECT id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at 
+            FROM messages 
+            WHERE id = $1",
+        id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::list_by_ids", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_by_ids<'c, E>(ids: &[Uuid], exec: E) -> RequestResult<Vec<MessageEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "SELECT id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at 
+            FROM messages 
+            WHERE id = ANY($1)",
+        ids
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::get_for_update", skip_all, fields(db.system = "postgresql"))]
+pub async fn get_for_update<'c, E>(id: Uuid, exec: E) -> RequestResult<MessageEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "SELECT id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at 
+            FROM messages 
+            WHERE id = $1 
+            FOR UPDATE",
+        id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// top-level messages only, newest first; `before` is the id of the oldest message the client already has
+#[tracing::instrument(name = "message_repository::list", skip_all, fields(db.system = "postgresql"))]
+pub async fn list<'c, E>(
+    room_id: Uuid,
+    before: Option<Uuid>,
+    limit: i64,
+    exec: E,
+) -> RequestResult<Vec<MessageEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "SELECT id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at 
+            FROM messages 
+            WHERE room_id = $1 AND parent_id IS NULL 
+                AND ($2::uuid IS NULL 
+                    OR (created_at, id) < (SELECT created_at, id FROM messages WHERE id = $2)) 
+            ORDER BY created_at DESC, id DESC 
+            LIMIT $3",
+        room_id,
+        before,
+        limit
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// replies of a thread, newest first, paginated like `list`
+#[tracing::instrument(name = "message_repository::list_replies", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_replies<'c, E>(
+    parent_id: Uuid,
+    before: Option<Uuid>,
+    limit: i64,
+    exec: E,
+) -> RequestResult<Vec<MessageEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "SELECT id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at 
+            FROM messages 
+            WHERE parent_id = $1 
+                AND ($2::uuid IS NULL 
+                    OR (created_at, id) < (SELECT created_at, id FROM messages WHERE id = $2)) 
+            ORDER BY created_at DESC, id DESC 
+            LIMIT $3",
+        parent_id,
+        before,
+        limit
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::record_reply", skip_all, fields(db.system = "postgresql"))]
+pub async fn record_reply<'c, E>(
+    parent_id: Uuid,
+    replied_at: DateTime<Utc>,
+    exec: E,
+) -> RequestResult<MessageEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "UPDATE messages 
+            SET reply_count = reply_count + 1, 
+                last_reply_at = GREATEST(last_reply_at, $2) 
+            WHERE id = $1 
+            RETURNING id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at",
+        parent_id,
+        replied_at
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// to be called after the reply is soft deleted, so the latest remaining one sets
+// `last_reply_at`
+#[tracing::instrument(name = "message_repository::forget_reply", skip_all, fields(db.system = "postgresql"))]
+pub async fn forget_reply<'c, E>(parent_id: Uuid, exec: E) -> RequestResult<MessageEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "UPDATE messages 
+            SET reply_count = GREATEST(reply_count - 1, 0), 
+                last_reply_at = ( 
+                    SELECT max(created_at) FROM messages 
+                        WHERE parent_id = $1 AND deleted_at IS NULL 
+                ) 
+            WHERE id = $1 
+            RETURNING id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at",
+        parent_id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::update_content", skip_all, fields(db.system = "postgresql"))]
+pub async fn update_content<'c, E>(
+    id: Uuid,
+    content: &str,
+    content_html: &str,
+    exec: E,
+) -> RequestResult<MessageEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "UPDATE messages 
+            SET content = $2, content_html = $3, edited_at = now() 
+            WHERE id = $1 
+            RETURNING id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at",
+        id,
+        content,
+        content_html
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::soft_delete", skip_all, fields(db.system = "postgresql"))]
+pub async fn soft_delete<'c, E>(id: Uuid, deleted_by: Uuid, exec: E) -> RequestResult<MessageEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageEntity,
+        "UPDATE messages 
+            SET deleted_at = now(), deleted_by = $2 
+            WHERE id = $1 AND deleted_at IS NULL 
+            RETURNING id, room_id, user_id, content, content_html, created_at, edited_at, deleted_at, deleted_by, 
+                parent_id, reply_count, last_reply_at",
+        id,
+        deleted_by
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// blanks the content of messages deleted before `deleted_before` and drops their edit
+// history, mentions, links and attachments; the attachment objects are queued in
+// `discarded_objects`
+#[tracing::instrument(name = "message_repository::purge_deleted", skip_all, fields(db.system = "postgresql"))]
+pub async fn purge_deleted<'c, E>(deleted_before: DateTime<Utc>, exec: E) -> RequestResult<i64>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        r#"WITH purged AS (
+                UPDATE messages 
+                    SET content = '', content_html = '', purged_at = now() 
+                    WHERE deleted_at < $1 AND purged_at IS NULL 
+                    RETURNING id
+            ), dropped_revisions AS (
+                DELETE FROM message_revisions 
+                    WHERE message_id IN (SELECT id FROM purged)
+            ), dropped_reactions AS (
+                DELETE FROM message_reactions 
+                    WHERE message_id IN (SELECT id FROM purged)
+            ), dropped_mentions AS (
+                DELETE FROM message_mentions 
+                    WHERE message_id IN (SELECT id FROM purged)
+            ), dropped_links AS (
+                DELETE FROM message_links 
+                    WHERE message_id IN (SELECT id FROM purged)
+            ), dropped_attachments AS (
+                DELETE FROM attachments 
+                    WHERE message_id IN (SELECT id FROM purged) 
+                    RETURNING id, storage_key
+            ), discarded AS (
+                INSERT INTO discarded_objects (storage_key) 
+                    SELECT storage_key FROM dropped_attachments 
+                    UNION ALL 
+                    SELECT t.storage_key FROM attachment_thumbnails t 
+                        JOIN dropped_attachments a ON a.id = t.attachment_id 
+                    ON CONFLICT DO NOTHING
+            )
+            SELECT count(*) AS "count!" FROM purged"#,
+        deleted_before
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::create_revision", skip_all, fields(db.system = "postgresql"))]
+pub async fn create_revision<'c, E>(
+    message_id: Uuid,
+    content: &str,
+    edited_by: Uuid,
+    exec: E,
+) -> RequestResult<Uuid>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "INSERT INTO message_revisions (message_id, content, edited_by) 
+            VALUES ($1, $2, $3) 
+            RETURNING id",
+        message_id,
+        content,
+        edited_by
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "message_repository::list_revisions", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_revisions<'c, E>(
+    message_id: Uuid,
+    exec: E,
+) -> RequestResult<Vec<MessageRevisionResponse>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        MessageRevisionResponse,
+        "SELECT id, message_id, content, edited_by, created_at 
+            FROM message_revisions 
+            WHERE message_id = $1 
+            ORDER BY created_at, id",
+        message_id
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// matches newest first in the rooms of `user_id`, paginated like `list`
+#[tracing::instrument(name = "message_repository::search", skip_all, fields(db.system = "postgresql"))]
+pub async fn search<'c, E>(
+    user_id: Uuid,
+    query: &ValidSearchMessagesQuery,
+    exec: E,
+) -> RequestResult<Vec<SearchHitEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        SearchHitEntity,
+        r#"SELECT m.id, m.room_id, m.user_id, m.content, m.content_html, m.created_at, m.edited_at, m.deleted_at, 
+                m.deleted_by, m.parent_id, m.reply_count, m.last_reply_at, 
+                ts_headline('english', 
+                    replace(replace(replace(m.content, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), 
+                    q.query, 
+                    'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15') 
+                    AS "snippet!" 
+            FROM messages m 
+            JOIN room_members rm ON rm.room_id = m.room_id AND rm.user_id = $1 
+            CROSS JOIN websearch_to_tsquery('english', $2) AS q(query) 
+            WHERE m.content_tsv @@ q.query AND m.deleted_at IS NULL 
+                AND ($3::uuid IS NULL OR m.room_id = $3) 
+                AND ($4::uuid IS NULL OR m.user_id = $4) 
+                AND ($5::uuid IS NULL 
+                    OR (m.created_at, m.id) < (SELECT created_at, id FROM messages WHERE id = $5)) 
+            ORDER BY m.created_at DESC, m.id DESC 
+            LIMIT $6"#,
+        user_id,
+        query.q.as_ref(),
+        query.room,
+        query.from,
+        query.before,
+        i64::from(query.limit)
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::mime;
+    use bytes::Bytes;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::app::{
+        media::images::Thumbnail,
+        models::attachments::ValidUploadedFile,
+        repositories::{
+            attachment_repository, discarded_object_repository, link_preview_repository,
+            reaction_repository, room_repository, user_repository,
+        },
+    };
+
+    #[sqlx::test]
+    async fn test_list_pages_backwards(pool: PgPool) {
+        let user_id = user_repository::create("rost@gmail.com", "somepass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, user_id, &pool)
+            .await
+            .unwrap();
+
+        for i in 0..5 {
+            create(room.id, user_id, &format!("message {i}"), "", None, &pool)
+                .await
+                .unwrap();
+        }
+
+        let first_page = list(room.id, None, 2, &pool).await.unwrap();
+        let second_page = list(room.id, first_page.last().map(|m| m.id), 2, &pool)
+            .await
+            .unwrap();
+        let last_page = list(room.id, second_page.last().map(|m| m.id), 2, &pool)
+            .await
+            .unwrap();
+
+        let contents = [first_page, second_page, last_page]
+            .into_iter()
+            .map(|page| page.into_iter().map(|m| m.content).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            vec![
+                vec!["message 4", "message 3"],
+                vec!["message 2", "message 1"],
+                vec!["message 0"],
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_soft_delete_and_purge(pool: PgPool) {
+        let user_id = user_repository::create("rost@gmail.com", "somepass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, user_id, &pool)
+            .await
+            .unwrap();
+        let kept = create(room.id, user_id, "kept", "", None, &pool)
+            .await
+            .unwrap();
+        let deleted = create(room.id, user_id, "secret", "", None, &pool)
+            .await
+            .unwrap();
+        create_revision(deleted.id, "older secret", user_id, &pool)
+            .await
+            .unwrap();
+
+        // everything else that hangs off the message goes with the content
+        sqlx::query!(
+            "INSERT INTO message_mentions (message_id, user_id) VALUES ($1, $2)",
+            deleted.id,
+            user_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        reaction_repository::add(deleted.id, user_id, "👍", &pool)
+            .await
+            .unwrap();
+        let links = ["https://example.com/".to_string()];
+        link_preview_repository::enqueue(&links, Utc::now(), &pool)
+            .await
+            .unwrap();
+        link_preview_repository::link(deleted.id, &links, &pool)
+            .await
+            .unwrap();
+        let file = ValidUploadedFile {
+            filename: "secret.png".to_string().try_into().unwrap(),
+            content_type: mime::IMAGE_PNG,
+            data: Bytes::from_static(b"png"),
+        };
+        let attachment = attachment_repository::create(room.id, user_id, &file, "r/secret", &pool)
+            .await
+            .unwrap();
+        attachment_repository::link(deleted.id, room.id, user_id, &[attachment.id], &pool)
+            .await
+            .unwrap();
+        let thumbnail = Thumbnail {
+            size: "small",
+            width: 1,
+            height: 1,
+            content_type: "image/png",
+            data: Bytes::from_static(b"png"),
+        };
+        attachment_repository::create_thumbnail(attachment.id, &thumbnail, "r/secret.small", &pool)
+            .await
+            .unwrap();
+
+        let tombstone = soft_delete(deleted.id, user_id, &pool).await.unwrap();
+        assert_eq!(tombstone.deleted_by, Some(user_id));
+        assert!(soft_delete(deleted.id, user_id, &pool).await.is_err());
+
+        // nothing is old enough yet
+        let purged = purge_deleted(Utc::now() - chrono::Duration::hours(1), &pool)
+            .await
+            .unwrap();
+        assert_eq!(purged, 0);
+        assert_eq!(get(deleted.id, &pool).await.unwrap().content, "secret");
+
+        let purged = purge_deleted(Utc::now(), &pool).await.unwrap();
+        assert_eq!(purged, 1);
+        assert_eq!(get(deleted.id, &pool).await.unwrap().content, "");
+        assert_eq!(get(kept.id, &pool).await.unwrap().content, "kept");
+        assert!(list_revisions(deleted.id, &pool).await.unwrap().is_empty());
+        let leftovers = sqlx::query_scalar!(
+            r#"SELECT (SELECT count(*) FROM message_reactions WHERE message_id = $1) 
+                    + (SELECT count(*) FROM message_mentions WHERE message_id = $1) 
+                    + (SELECT count(*) FROM message_links WHERE message_id = $1) 
+                    + (SELECT count(*) FROM attachments WHERE message_id = $1) 
+                    + (SELECT count(*) FROM attachment_thumbnails) AS "count!""#,
+            deleted.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(leftovers, 0);
+        let mut discarded = discarded_object_repository::list(10, &pool).await.unwrap();
+        discarded.sort();
+        assert_eq!(discarded, vec!["r/secret", "r/secret.small"]);
+
+        // the tombstone keeps its place in history
+        let history = list(room.id, None, 10, &pool).await.unwrap();
+        assert_eq!(history.len(), 2);
+    }
+}
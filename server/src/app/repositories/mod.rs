@@ -1,2 +1,12 @@
+pub mod attachment_repository;
+pub mod discarded_object_repository;
+pub mod link_preview_repository;
+pub mod mention_repository;
+pub mod message_repository;
+pub mod pin_repository;
 pub mod profile_repository;
+pub mod reaction_repository;
+pub mod role_repository;
+pub mod room_repository;
 pub mod user_repository;
+pub mod ws_ticket_repository;
@@ -0,0 +1,66 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::{models::messages::PinEntity, request_error::RequestResult};
+
+// returns None when the message is already pinned
+#[tracing::instrument(name = "pin_repository::create", skip_all, fields(db.system = "postgresql"))]
+pub async fn create<'c, E>(
+    message_id: Uuid,
+    room_id: Uuid,
+    pinned_by: Uuid,
+    exec: E,
+) -> RequestResult<Option<PinEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        PinEntity,
+        "INSERT INTO message_pins (message_id, room_id, pinned_by) 
+            VALUES ($1, $2, $3) 
+            ON CONFLICT DO NOTHING 
+            RETURNING message_id, room_id, pinned_by, pinned_at",
+        message_id,
+        room_id,
+        pinned_by
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "pin_repository::delete", skip_all, fields(db.system = "postgresql"))]
+pub async fn delete<'c, E>(message_id: Uuid, exec: E) -> RequestResult<Option<PinEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        PinEntity,
+        "DELETE FROM message_pins 
+            WHERE message_id = $1 
+            RETURNING message_id, room_id, pinned_by, pinned_at",
+        message_id
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+// oldest pin first
+#[tracing::instrument(name = "pin_repository::list", skip_all, fields(db.system = "postgresql"))]
+pub async fn list<'c, E>(room_id: Uuid, exec: E) -> RequestResult<Vec<PinEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        PinEntity,
+        "SELECT message_id, room_id, pinned_by, pinned_at 
+            FROM message_pins 
+            WHERE room_id = $1 
+            ORDER BY pinned_at",
+        room_id
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
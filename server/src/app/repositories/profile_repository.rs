@@ -6,6 +6,7 @@ use crate::app::{
     request_error::RequestResult,
 };
 
+#[tracing::instrument(name = "profile_repository::get_by_user_id", skip_all, fields(db.system = "postgresql"))]
 pub async fn get_by_user_id<'c, E>(user_id: Uuid, exec: E) -> RequestResult<ProfileEntity>
 where
     E: PgExecutor<'c>,
@@ -21,6 +22,7 @@ where
     .map_err(From::from)
 }
 
+#[tracing::instrument(name = "profile_repository::create", skip_all, fields(db.system = "postgresql"))]
 pub async fn create<'c, E>(
     user_id: Uuid,
     profile: ValidCreateProfileRequest,
@@ -42,3 +44,68 @@ where
     .await
     .map_err(From::from)
 }
+
+// `avatar_key` and `avatar_content_type` are both set or both cleared; also returns the
+// key that was replaced, read under the row lock so that concurrent uploads each see the
+// one they replace
+#[tracing::instrument(name = "profile_repository::set_avatar", skip_all, fields(db.system = "postgresql"))]
+pub async fn set_avatar<'c, E>(
+    user_id: Uuid,
+    avatar_key: Option<&str>,
+    avatar_content_type: Option<&str>,
+    exec: E,
+) -> RequestResult<(ProfileEntity, Option<String>)>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "UPDATE profiles p 
+            SET avatar_key = $2, 
+                avatar_content_type = $3, 
+                avatar_updated_at = CASE WHEN $2::TEXT IS NULL THEN NULL ELSE now() END, 
+                updated_at = now() 
+            FROM (SELECT id, avatar_key FROM profiles WHERE user_id = $1 FOR UPDATE) previous 
+            WHERE p.id = previous.id 
+            RETURNING p.id, p.user_id, p.username, p.age, p.about_me, p.updated_at, 
+                p.created_at, p.avatar_key, p.avatar_content_type, p.avatar_updated_at, 
+                previous.avatar_key AS previous_avatar_key",
+        user_id,
+        avatar_key,
+        avatar_content_type
+    )
+    .fetch_one(exec)
+    .await
+    .map(|row| {
+        let profile = ProfileEntity {
+            id: row.id,
+            user_id: row.user_id,
+            username: row.username,
+            age: row.age,
+            about_me: row.about_me,
+            updated_at: row.updated_at,
+            created_at: row.created_at,
+            avatar_key: row.avatar_key,
+            avatar_content_type: row.avatar_content_type,
+            avatar_updated_at: row.avatar_updated_at,
+        };
+        (profile, row.previous_avatar_key)
+    })
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "profile_repository::find_avatar", skip_all, fields(db.system = "postgresql"))]
+pub async fn find_avatar<'c, E>(user_id: Uuid, exec: E) -> RequestResult<Option<(String, String)>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "SELECT avatar_key AS \"avatar_key!\", avatar_content_type AS \"avatar_content_type!\" 
+            FROM profiles 
+            WHERE user_id = $1 AND avatar_key IS NOT NULL",
+        user_id
+    )
+    .fetch_optional(exec)
+    .await
+    .map(|row| row.map(|row| (row.avatar_key, row.avatar_content_type)))
+    .map_err(From::from)
+}
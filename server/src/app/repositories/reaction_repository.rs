@@ -0,0 +1,108 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::{
+    models::reactions::{ReactionCountEntity, ReactionResponse},
+    request_error::RequestResult,
+};
+
+// returns None when the user has already reacted to the message with this emoji
+#[tracing::instrument(name = "reaction_repository::add", skip_all, fields(db.system = "postgresql"))]
+pub async fn add<'c, E>(
+    message_id: Uuid,
+    user_id: Uuid,
+    emoji: &str,
+    exec: E,
+) -> RequestResult<Option<ReactionResponse>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ReactionResponse,
+        r#"WITH reaction AS (
+                INSERT INTO message_reactions (message_id, user_id, emoji) 
+                    VALUES ($1, $2, $3) 
+                    ON CONFLICT DO NOTHING 
+                    RETURNING message_id, user_id, emoji, created_at
+            )
+            SELECT r.message_id AS "message_id!", m.room_id, r.user_id AS "user_id!", 
+                r.emoji AS "emoji!", r.created_at AS "created_at!" 
+            FROM reaction r 
+            JOIN messages m ON m.id = r.message_id"#,
+        message_id,
+        user_id,
+        emoji
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "reaction_repository::remove", skip_all, fields(db.system = "postgresql"))]
+pub async fn remove<'c, E>(
+    message_id: Uuid,
+    user_id: Uuid,
+    emoji: &str,
+    exec: E,
+) -> RequestResult<Option<ReactionResponse>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ReactionResponse,
+        r#"WITH reaction AS (
+                DELETE FROM message_reactions 
+                    WHERE message_id = $1 AND user_id = $2 AND emoji = $3 
+                    RETURNING message_id, user_id, emoji, created_at
+            )
+            SELECT r.message_id AS "message_id!", m.room_id, r.user_id AS "user_id!", 
+                r.emoji AS "emoji!", r.created_at AS "created_at!" 
+            FROM reaction r 
+            JOIN messages m ON m.id = r.message_id"#,
+        message_id,
+        user_id,
+        emoji
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+// reaction counts of many messages at once, `reacted` is relative to `user_id`
+#[tracing::instrument(name = "reaction_repository::count_for_messages", skip_all, fields(db.system = "postgresql"))]
+pub async fn count_for_messages<'c, E>(
+    message_ids: &[Uuid],
+    user_id: Uuid,
+    exec: E,
+) -> RequestResult<Vec<ReactionCountEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ReactionCountEntity,
+        r#"SELECT message_id, emoji, COUNT(*) AS "count!", BOOL_OR(user_id = $2) AS "reacted!" 
+            FROM message_reactions 
+            WHERE message_id = ANY($1) 
+            GROUP BY message_id, emoji 
+            ORDER BY message_id, MIN(created_at), emoji"#,
+        message_ids,
+        user_id
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "reaction_repository::custom_emoji_exists", skip_all, fields(db.system = "postgresql"))]
+pub async fn custom_emoji_exists<'c, E>(shortcode: &str, exec: E) -> RequestResult<bool>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM custom_emojis WHERE shortcode = $1) AS "exists!""#,
+        shortcode
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
@@ -0,0 +1,43 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::request_error::RequestResult;
+
+#[tracing::instrument(name = "role_repository::assign", skip_all, fields(db.system = "postgresql"))]
+pub async fn assign<'c, E>(user_id: Uuid, rolename: &str, exec: E) -> RequestResult<()>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "INSERT INTO users_roles (user_id, role_id) 
+            SELECT $1, id FROM roles WHERE rolename = $2 
+            ON CONFLICT DO NOTHING",
+        user_id,
+        rolename
+    )
+    .execute(exec)
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "role_repository::has_any", skip_all, fields(db.system = "postgresql"))]
+pub async fn has_any<'c, E>(user_id: Uuid, rolenames: &[&str], exec: E) -> RequestResult<bool>
+where
+    E: PgExecutor<'c>,
+{
+    let rolenames = rolenames.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+                SELECT 1 FROM users_roles ur
+                JOIN roles r ON r.id = ur.role_id
+                WHERE ur.user_id = $1 AND r.rolename = ANY($2)
+            ) AS "exists!""#,
+        user_id,
+        &rolenames
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
@@ -0,0 +1,298 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::{
+    models::{
+        presence::PresenceEntity,
+        rooms::{
+            ReadReceiptResponse, RoomInviteResponse, RoomMemberEntity, RoomResponse,
+            UnreadCountResponse, domain::RoomRole,
+        },
+    },
+    request_error::RequestResult,
+};
+
+#[tracing::instrument(name = "room_repository::create", skip_all, fields(db.system = "postgresql"))]
+pub async fn create<'c, E>(
+    name: &str,
+    is_private: bool,
+    created_by: Uuid,
+    exec: E,
+) -> RequestResult<RoomResponse>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        RoomResponse,
+        "INSERT INTO rooms (name, is_private, created_by) 
+            VALUES ($1, $2, $3) 
+            RETURNING id, name, is_private, created_by, created_at",
+        name,
+        is_private,
+        created_by
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "room_repository::get", skip_all, fields(db.system = "postgresql"))]
+pub async fn get<'c, E>(id: Uuid, exec: E) -> RequestResult<RoomResponse>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        RoomResponse,
+        "SELECT id, name, is_private, created_by, created_at 
+            FROM rooms 
+            WHERE id = $1",
+        id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "room_repository::list_for_user", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_for_user<'c, E>(user_id: Uuid, exec: E) -> RequestResult<Vec<RoomResponse>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        RoomResponse,
+        "SELECT r.id, r.name, r.is_private, r.created_by, r.created_at 
+            FROM rooms r 
+            JOIN room_members rm ON rm.room_id = r.id 
+            WHERE rm.user_id = $1 
+            ORDER BY r.created_at",
+        user_id
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "room_repository::room_ids_for_user", skip_all, fields(db.system = "postgresql"))]
+pub async fn room_ids_for_user<'c, E>(user_id: Uuid, exec: E) -> RequestResult<Vec<Uuid>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "SELECT room_id FROM room_members 
+            WHERE user_id = $1",
+        user_id
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "room_repository::add_member", skip_all, fields(db.system = "postgresql"))]
+pub async fn add_member<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    role: RoomRole,
+    exec: E,
+) -> RequestResult<()>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "INSERT INTO room_members (room_id, user_id, role) 
+            VALUES ($1, $2, $3) 
+            ON CONFLICT DO NOTHING",
+        room_id,
+        user_id,
+        role.as_str()
+    )
+    .execute(exec)
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "room_repository::remove_member", skip_all, fields(db.system = "postgresql"))]
+pub async fn remove_member<'c, E>(room_id: Uuid, user_id: Uuid, exec: E) -> RequestResult<Uuid>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "DELETE FROM room_members 
+            WHERE room_id = $1 AND user_id = $2 
+            RETURNING room_id",
+        room_id,
+        user_id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// inviting again refreshes the invite
+#[tracing::instrument(name = "room_repository::create_invite", skip_all, fields(db.system = "postgresql"))]
+pub async fn create_invite<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    invited_by: Uuid,
+    exec: E,
+) -> RequestResult<RoomInviteResponse>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        RoomInviteResponse,
+        "INSERT INTO room_invites (room_id, user_id, invited_by) 
+            VALUES ($1, $2, $3) 
+            ON CONFLICT (room_id, user_id) 
+            DO UPDATE SET invited_by = EXCLUDED.invited_by, created_at = now() 
+            RETURNING room_id, user_id, invited_by, created_at",
+        room_id,
+        user_id,
+        invited_by
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// deletes the invite; false when there was none
+#[tracing::instrument(name = "room_repository::take_invite", skip_all, fields(db.system = "postgresql"))]
+pub async fn take_invite<'c, E>(room_id: Uuid, user_id: Uuid, exec: E) -> RequestResult<bool>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "DELETE FROM room_invites 
+            WHERE room_id = $1 AND user_id = $2 
+            RETURNING room_id",
+        room_id,
+        user_id
+    )
+    .fetch_optional(exec)
+    .await
+    .map(|room_id| room_id.is_some())
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "room_repository::get_member", skip_all, fields(db.system = "postgresql"))]
+pub async fn get_member<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    exec: E,
+) -> RequestResult<Option<RoomMemberEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        RoomMemberEntity,
+        "SELECT room_id, user_id, role, joined_at 
+            FROM room_members 
+            WHERE room_id = $1 AND user_id = $2",
+        room_id,
+        user_id
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+// moves the read marker forward; returns None when `message_id` is not newer than it
+#[tracing::instrument(name = "room_repository::mark_read", skip_all, fields(db.system = "postgresql"))]
+pub async fn mark_read<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    message_id: Uuid,
+    exec: E,
+) -> RequestResult<Option<ReadReceiptResponse>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ReadReceiptResponse,
+        "UPDATE room_members rm 
+            SET last_read_message_id = $3, last_read_at = now() 
+            WHERE rm.room_id = $1 AND rm.user_id = $2 
+                AND (rm.last_read_message_id IS NULL 
+                    OR (SELECT (created_at, id) FROM messages WHERE id = $3) 
+                        > (SELECT (created_at, id) FROM messages WHERE id = rm.last_read_message_id)) 
+            RETURNING room_id, user_id, last_read_message_id, last_read_at",
+        room_id,
+        user_id,
+        message_id
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "room_repository::get_read_receipt", skip_all, fields(db.system = "postgresql"))]
+pub async fn get_read_receipt<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    exec: E,
+) -> RequestResult<ReadReceiptResponse>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        ReadReceiptResponse,
+        "SELECT room_id, user_id, last_read_message_id, last_read_at 
+            FROM room_members 
+            WHERE room_id = $1 AND user_id = $2",
+        room_id,
+        user_id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// top-level messages of other users after the read marker, or after joining when
+// nothing has been read yet, for every room of the user
+#[tracing::instrument(name = "room_repository::unread_counts", skip_all, fields(db.system = "postgresql"))]
+pub async fn unread_counts<'c, E>(user_id: Uuid, exec: E) -> RequestResult<Vec<UnreadCountResponse>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        UnreadCountResponse,
+        r#"SELECT rm.room_id, rm.last_read_message_id, COUNT(m.id) AS "unread_count!" 
+            FROM room_members rm 
+            LEFT JOIN messages r ON r.id = rm.last_read_message_id 
+            LEFT JOIN messages m ON m.room_id = rm.room_id 
+                AND m.parent_id IS NULL 
+                AND m.deleted_at IS NULL 
+                AND m.user_id <> rm.user_id 
+                AND CASE WHEN r.id IS NULL THEN m.created_at > rm.joined_at 
+                    ELSE (m.created_at, m.id) > (r.created_at, r.id) END 
+            WHERE rm.user_id = $1 
+            GROUP BY rm.room_id, rm.last_read_message_id 
+            ORDER BY rm.room_id"#,
+        user_id
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "room_repository::list_member_presence", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_member_presence<'c, E>(
+    room_id: Uuid,
+    exec: E,
+) -> RequestResult<Vec<PresenceEntity>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        PresenceEntity,
+        "SELECT u.id AS user_id, u.presence_override, u.last_seen_at 
+            FROM room_members rm 
+            JOIN users u ON u.id = rm.user_id 
+            WHERE rm.room_id = $1 
+            ORDER BY rm.joined_at",
+        room_id
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
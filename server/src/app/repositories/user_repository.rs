@@ -1,11 +1,16 @@
+use chrono::{DateTime, Utc};
 use sqlx::{PgExecutor, Postgres};
 use uuid::Uuid;
 
 use crate::app::{
-    models::users::{UserEntity, ValidPatchUserRequest},
+    models::{
+        presence::PresenceEntity,
+        users::{UserEntity, UserSummary, ValidPatchUserRequest},
+    },
     request_error::RequestResult,
 };
 
+#[tracing::instrument(name = "user_repository::get", skip_all, fields(db.system = "postgresql"))]
 pub async fn get<'c, E>(id: Uuid, exec: E) -> RequestResult<UserEntity>
 where
     E: PgExecutor<'c>,
@@ -22,6 +27,7 @@ where
     .map_err(From::from)
 }
 
+#[tracing::instrument(name = "user_repository::get_by_email", skip_all, fields(db.system = "postgresql"))]
 pub async fn get_by_email<'c, E>(email: &str, exec: E) -> RequestResult<UserEntity>
 where
     E: PgExecutor<'c>,
@@ -37,6 +43,7 @@ where
     .map_err(From::from)
 }
 
+#[tracing::instrument(name = "user_repository::create", skip_all, fields(db.system = "postgresql"))]
 pub async fn create<'c, E>(email: &str, password: &str, exec: E) -> RequestResult<Uuid>
 where
     E: PgExecutor<'c>,
@@ -53,6 +60,7 @@ where
     .map_err(From::from)
 }
 
+#[tracing::instrument(name = "user_repository::patch", skip_all, fields(db.system = "postgresql"))]
 pub async fn patch<'c, E>(id: Uuid, user: ValidPatchUserRequest, exec: E) -> RequestResult<Uuid>
 where
     E: PgExecutor<'c>,
@@ -86,6 +94,7 @@ where
         .map_err(From::from)
 }
 
+#[tracing::instrument(name = "user_repository::delete", skip_all, fields(db.system = "postgresql"))]
 pub async fn delete<'c, E>(id: Uuid, exec: E) -> RequestResult<Uuid>
 where
     E: PgExecutor<'c>,
@@ -101,6 +110,147 @@ where
     .map_err(From::from)
 }
 
+#[tracing::instrument(name = "user_repository::list", skip_all, fields(db.system = "postgresql"))]
+pub async fn list<'c, E>(limit: i64, offset: i64, exec: E) -> RequestResult<Vec<UserSummary>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        UserSummary,
+        r#"SELECT u.id, u.email, u.created_at, u.disabled_at,
+                COALESCE(array_agg(r.rolename ORDER BY r.rolename)
+                    FILTER (WHERE r.rolename IS NOT NULL), '{}') AS "roles!"
+            FROM users u
+            LEFT JOIN users_roles ur ON ur.user_id = u.id
+            LEFT JOIN roles r ON r.id = ur.role_id
+            GROUP BY u.id
+            ORDER BY u.created_at
+            LIMIT $1 OFFSET $2"#,
+        limit,
+        offset
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "user_repository::disable", skip_all, fields(db.system = "postgresql"))]
+pub async fn disable<'c, E>(email: &str, exec: E) -> RequestResult<Uuid>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "UPDATE users 
+            SET disabled_at = COALESCE(disabled_at, now()), updated_at = now() 
+            WHERE email = $1 
+            RETURNING id",
+        email
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+// None when the user does not exist
+#[tracing::instrument(name = "user_repository::is_disabled", skip_all, fields(db.system = "postgresql"))]
+pub async fn is_disabled<'c, E>(id: Uuid, exec: E) -> RequestResult<Option<bool>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        r#"SELECT disabled_at IS NOT NULL AS "disabled!" FROM users 
+            WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "user_repository::list_disabled", skip_all, fields(db.system = "postgresql"))]
+pub async fn list_disabled<'c, E>(exec: E) -> RequestResult<Vec<Uuid>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "SELECT id FROM users 
+            WHERE disabled_at IS NOT NULL"
+    )
+    .fetch_all(exec)
+    .await
+    .map_err(From::from)
+}
+
+// delivered to listeners when the transaction commits
+#[tracing::instrument(name = "user_repository::notify_disabled", skip_all, fields(db.system = "postgresql"))]
+pub async fn notify_disabled<'c, E>(channel: &str, id: Uuid, exec: E) -> RequestResult<()>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!("SELECT pg_notify($1, $2::UUID::TEXT)", channel, id)
+        .execute(exec)
+        .await
+        .map(|_| ())
+        .map_err(From::from)
+}
+
+#[tracing::instrument(name = "user_repository::get_presence", skip_all, fields(db.system = "postgresql"))]
+pub async fn get_presence<'c, E>(id: Uuid, exec: E) -> RequestResult<PresenceEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        PresenceEntity,
+        "SELECT id AS user_id, presence_override, last_seen_at 
+            FROM users 
+            WHERE id = $1",
+        id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "user_repository::set_presence_override", skip_all, fields(db.system = "postgresql"))]
+pub async fn set_presence_override<'c, E>(
+    id: Uuid,
+    presence_override: Option<&str>,
+    exec: E,
+) -> RequestResult<PresenceEntity>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_as!(
+        PresenceEntity,
+        "UPDATE users 
+            SET presence_override = $2 
+            WHERE id = $1 
+            RETURNING id AS user_id, presence_override, last_seen_at",
+        id,
+        presence_override
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
+#[tracing::instrument(name = "user_repository::touch_last_seen", skip_all, fields(db.system = "postgresql"))]
+pub async fn touch_last_seen<'c, E>(id: Uuid, exec: E) -> RequestResult<DateTime<Utc>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        r#"UPDATE users 
+            SET last_seen_at = now() 
+            WHERE id = $1 
+            RETURNING last_seen_at AS "last_seen_at!""#,
+        id
+    )
+    .fetch_one(exec)
+    .await
+    .map_err(From::from)
+}
+
 #[cfg(test)]
 mod tests {
     use expect_test::expect;
@@ -108,32 +258,42 @@ mod tests {
 
     use super::*;
 
+    // ids and timestamps differ on every run
+    fn redacted(user: UserEntity) -> String {
+        let user = UserEntity {
+            id: Uuid::nil(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            ..user
+        };
+        format!("{:#?}", user)
+    }
+
     #[sqlx::test]
     async fn test_create(pool: PgPool) {
         let user_id = create("rost@gmail.com", "somepassword", &pool)
             .await
             .unwrap();
 
-        let exp = expect!["a936afc8-6079-477a-98af-5c8c8620f67b"];
-        exp.assert_eq(&user_id.to_string());
+        assert_eq!(get(user_id, &pool).await.unwrap().id, user_id);
     }
 
     #[sqlx::test]
     async fn test_get(pool: PgPool) {
         let user_id = create("rost@gmail.com", "somepass", &pool).await.unwrap();
-        let exp = expect!["3d162d80-1916-43b0-9824-d45f29f31fd0"];
-        exp.assert_eq(&user_id.to_string());
-
         let user = get(user_id, &pool).await.unwrap();
         let exp = expect![[r#"
             UserEntity {
-                id: 3d162d80-1916-43b0-9824-d45f29f31fd0,
+                id: 00000000-0000-0000-0000-000000000000,
                 email: "rost@gmail.com",
                 password: "somepass",
-                created_at: 2025-11-29T20:18:52.012315Z,
-                updated_at: 2025-11-29T20:18:52.012315Z,
+                created_at: 1970-01-01T00:00:00Z,
+                updated_at: 1970-01-01T00:00:00Z,
+                disabled_at: None,
+                last_seen_at: None,
+                presence_override: None,
             }"#]];
-        exp.assert_eq(&format!("{:#?}", user));
+        exp.assert_eq(&redacted(user));
     }
 
     #[sqlx::test]
@@ -141,30 +301,27 @@ mod tests {
         let email = "rost@gmail.com";
 
         let user_id = create(email, "somepass", &pool).await.unwrap();
-        let exp = expect!["217a7634-cd89-4e3a-b58e-d10ff460323c"];
-        exp.assert_eq(&user_id.to_string());
-
         let user = get_by_email(email, &pool).await.unwrap();
+        assert_eq!(user.id, user_id);
         let exp = expect![[r#"
             UserEntity {
-                id: 217a7634-cd89-4e3a-b58e-d10ff460323c,
+                id: 00000000-0000-0000-0000-000000000000,
                 email: "rost@gmail.com",
                 password: "somepass",
-                created_at: 2025-11-29T20:18:52.001497Z,
-                updated_at: 2025-11-29T20:18:52.001497Z,
+                created_at: 1970-01-01T00:00:00Z,
+                updated_at: 1970-01-01T00:00:00Z,
+                disabled_at: None,
+                last_seen_at: None,
+                presence_override: None,
             }"#]];
-        exp.assert_eq(&format!("{:#?}", user));
+        exp.assert_eq(&redacted(user));
     }
 
     #[sqlx::test]
     async fn test_delete(pool: PgPool) {
         let user_id = create("rost@gmail.com", "somepass", &pool).await.unwrap();
-        let exp = expect!["c1fc0c91-c2fa-439a-bae7-82793f051be6"];
-        exp.assert_eq(&user_id.to_string());
-
         let deleted_user_id = delete(user_id, &pool).await.unwrap();
-        let exp = expect!["c1fc0c91-c2fa-439a-bae7-82793f051be6"];
-        exp.assert_eq(&deleted_user_id.to_string());
+        assert_eq!(deleted_user_id, user_id);
 
         let try_get_info = get(deleted_user_id, &pool).await;
         assert!(try_get_info.is_err());
@@ -173,19 +330,20 @@ mod tests {
     #[sqlx::test]
     async fn test_patch(pool: PgPool) {
         let user_id = create("rost@gmail.com", "somepass", &pool).await.unwrap();
-        let exp = expect!["7949b2f2-ddac-477f-820d-b83edd5c6651"];
-        exp.assert_eq(&user_id.to_string());
-
         let user = get(user_id, &pool).await.unwrap();
+        let updated_at = user.updated_at;
         let exp = expect![[r#"
             UserEntity {
-                id: 7949b2f2-ddac-477f-820d-b83edd5c6651,
+                id: 00000000-0000-0000-0000-000000000000,
                 email: "rost@gmail.com",
                 password: "somepass",
-                created_at: 2025-11-29T20:18:52.027833Z,
-                updated_at: 2025-11-29T20:18:52.027833Z,
+                created_at: 1970-01-01T00:00:00Z,
+                updated_at: 1970-01-01T00:00:00Z,
+                disabled_at: None,
+                last_seen_at: None,
+                presence_override: None,
             }"#]];
-        exp.assert_eq(&format!("{:#?}", user));
+        exp.assert_eq(&redacted(user));
 
         let patch_info = ValidPatchUserRequest {
             email: Some("updatedRost@gmail.com".to_string().try_into().unwrap()),
@@ -194,14 +352,67 @@ mod tests {
 
         let patch_user_id = patch(user_id, patch_info, &pool).await.unwrap();
         let patch_user = get(patch_user_id, &pool).await.unwrap();
+        assert_eq!(patch_user_id, user_id);
+        assert!(patch_user.updated_at > updated_at);
         let exp = expect![[r#"
             UserEntity {
-                id: 7949b2f2-ddac-477f-820d-b83edd5c6651,
+                id: 00000000-0000-0000-0000-000000000000,
                 email: "updatedRost@gmail.com",
                 password: "updatedPassword",
-                created_at: 2025-11-29T20:18:52.027833Z,
-                updated_at: 2025-11-29T20:18:52.064844Z,
+                created_at: 1970-01-01T00:00:00Z,
+                updated_at: 1970-01-01T00:00:00Z,
+                disabled_at: None,
+                last_seen_at: None,
+                presence_override: None,
             }"#]];
-        exp.assert_eq(&format!("{:#?}", patch_user));
+        exp.assert_eq(&redacted(patch_user));
+    }
+
+    #[sqlx::test]
+    async fn test_disable(pool: PgPool) {
+        let user_id = create("rost@gmail.com", "somepass", &pool).await.unwrap();
+        create("other@gmail.com", "somepass", &pool).await.unwrap();
+        assert!(get(user_id, &pool).await.unwrap().disabled_at.is_none());
+        assert!(list_disabled(&pool).await.unwrap().is_empty());
+
+        let disabled_user_id = disable("rost@gmail.com", &pool).await.unwrap();
+        assert_eq!(disabled_user_id, user_id);
+
+        let disabled_at = get(user_id, &pool).await.unwrap().disabled_at;
+        assert!(disabled_at.is_some());
+        assert_eq!(is_disabled(user_id, &pool).await.unwrap(), Some(true));
+        assert_eq!(is_disabled(Uuid::new_v4(), &pool).await.unwrap(), None);
+        assert_eq!(list_disabled(&pool).await.unwrap(), vec![user_id]);
+
+        // disabling twice keeps the original timestamp
+        disable("rost@gmail.com", &pool).await.unwrap();
+        assert_eq!(get(user_id, &pool).await.unwrap().disabled_at, disabled_at);
+
+        let try_disable_unknown = disable("unknown@gmail.com", &pool).await;
+        assert!(try_disable_unknown.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_list(pool: PgPool) {
+        let admin_id = create("admin@gmail.com", "somepass", &pool).await.unwrap();
+        create("rost@gmail.com", "somepass", &pool).await.unwrap();
+        crate::app::repositories::role_repository::assign(admin_id, "admin", &pool)
+            .await
+            .unwrap();
+
+        let users = list(10, 0, &pool).await.unwrap();
+        let summary = users
+            .iter()
+            .map(|u| format!("{} {:?}", u.email, u.roles))
+            .collect::<Vec<_>>();
+        let exp = expect![[r#"
+            [
+                "admin@gmail.com [\"admin\"]",
+                "rost@gmail.com []",
+            ]"#]];
+        exp.assert_eq(&format!("{:#?}", summary));
+
+        let users = list(10, 1, &pool).await.unwrap();
+        assert_eq!(users.len(), 1);
     }
 }
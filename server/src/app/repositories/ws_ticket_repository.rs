@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::app::request_error::RequestResult;
+
+// expired tickets of every user are dropped on the way
+#[tracing::instrument(name = "ws_ticket_repository::create", skip_all, fields(db.system = "postgresql"))]
+pub async fn create<'c, E>(
+    ticket_hash: &str,
+    user_id: Uuid,
+    expires_at: DateTime<Utc>,
+    exec: E,
+) -> RequestResult<()>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query!(
+        "WITH expired AS ( 
+            DELETE FROM ws_tickets WHERE expires_at <= now() 
+        ) 
+        INSERT INTO ws_tickets (ticket_hash, user_id, expires_at) 
+            VALUES ($1, $2, $3)",
+        ticket_hash,
+        user_id,
+        expires_at
+    )
+    .execute(exec)
+    .await
+    .map(|_| ())
+    .map_err(From::from)
+}
+
+// a ticket is deleted as it is redeemed, so it works once
+#[tracing::instrument(name = "ws_ticket_repository::redeem", skip_all, fields(db.system = "postgresql"))]
+pub async fn redeem<'c, E>(ticket_hash: &str, exec: E) -> RequestResult<Option<Uuid>>
+where
+    E: PgExecutor<'c>,
+{
+    sqlx::query_scalar!(
+        "DELETE FROM ws_tickets 
+            WHERE ticket_hash = $1 AND expires_at > now() 
+            RETURNING user_id",
+        ticket_hash
+    )
+    .fetch_optional(exec)
+    .await
+    .map_err(From::from)
+}
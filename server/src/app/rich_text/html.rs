@@ -0,0 +1,91 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+
+use super::{Block, Inline};
+
+// every piece of user text goes through `encode_text` or `encode_double_quoted_attribute`
+// and the only tags are the ones written here, so the output is safe to insert as is
+pub fn render(blocks: &[Block]) -> String {
+    let mut html = String::new();
+
+    for block in blocks {
+        match block {
+            Block::Paragraph(inlines) => {
+                html.push_str("<p>");
+                render_inlines(inlines, &mut html);
+                html.push_str("</p>");
+            }
+            Block::CodeBlock { language, code } => {
+                match language {
+                    Some(language) => html.push_str(&format!(
+                        r#"<pre><code class="language-{}">"#,
+                        encode_double_quoted_attribute(language)
+                    )),
+                    None => html.push_str("<pre><code>"),
+                }
+                html.push_str(&encode_text(code));
+                html.push_str("</code></pre>");
+            }
+        }
+    }
+
+    html
+}
+
+fn render_inlines(inlines: &[Inline], html: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => html.push_str(&encode_text(text)),
+            Inline::Bold(children) => {
+                html.push_str("<strong>");
+                render_inlines(children, html);
+                html.push_str("</strong>");
+            }
+            Inline::Italic(children) => {
+                html.push_str("<em>");
+                render_inlines(children, html);
+                html.push_str("</em>");
+            }
+            Inline::Code(code) => {
+                html.push_str("<code>");
+                html.push_str(&encode_text(code));
+                html.push_str("</code>");
+            }
+            Inline::Link { url, children } => {
+                html.push_str(&format!(
+                    r#"<a href="{}" rel="nofollow noopener noreferrer" target="_blank">"#,
+                    encode_double_quoted_attribute(url)
+                ));
+                render_inlines(children, html);
+                html.push_str("</a>");
+            }
+            Inline::Mention(username) => html.push_str(&format!(
+                r#"<span class="mention" data-username="{}">@{}</span>"#,
+                encode_double_quoted_attribute(username),
+                encode_text(username)
+            )),
+            Inline::LineBreak => html.push_str("<br>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use crate::app::rich_text::to_html;
+
+    #[test]
+    fn test_render() {
+        expect![[r#"<p><strong>bold</strong> <em>it</em> <code>&lt;b&gt;</code> <span class="mention" data-username="bob">@bob</span><br><a href="https://example.com/?a=1&amp;b=%22x%22" rel="nofollow noopener noreferrer" target="_blank">site</a></p><pre><code class="language-html">&lt;p&gt;&amp;&lt;/p&gt;</code></pre>"#]]
+            .assert_eq(&to_html(
+                "**bold** _it_ `<b>` @bob\n[site](https://example.com/?a=1&b=\"x\")\n```html\n<p>&</p>\n```",
+            ));
+
+        // none of these get a tag or an attribute of their own
+        expect![[r#"<p>&lt;script&gt;alert(1)&lt;/script&gt; &lt;img src=x onerror=alert(1)&gt; [x](javascript:alert(1)) <a href="https://a.test/%22onmouseover=%22alert(1)" rel="nofollow noopener noreferrer" target="_blank">https://a.test/"onmouseover="alert(1)</a></p>"#]]
+            .assert_eq(&to_html(
+                "<script>alert(1)</script> <img src=x onerror=alert(1)> [x](javascript:alert(1)) \
+                    https://a.test/\"onmouseover=\"alert(1)",
+            ));
+    }
+}
@@ -0,0 +1,311 @@
+mod html;
+
+use reqwest::Url;
+
+pub use html::render;
+
+// closing punctuation of the sentence around a link is not part of it
+const LINK_TRAILING_PUNCTUATION: &[char] =
+    &['.', ',', ':', ';', '!', '?', '\'', '"', ')', ']', '>', '*'];
+const CODE_FENCE: &str = "```";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Paragraph(Vec<Inline>),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Code(String),
+    // http and https only
+    Link { url: String, children: Vec<Inline> },
+    Mention(String),
+    LineBreak,
+}
+
+// the message format: **bold**, *italic* or _italic_, `code`, ``` fenced code blocks,
+// [text](url) and bare http(s) links and @mentions; anything else is plain text
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph = Vec::new();
+    let mut lines = text.lines();
+
+    while let Some(line) = lines.next() {
+        let info = match line.trim_start().strip_prefix(CODE_FENCE) {
+            // a line like ```code``` is inline code
+            Some(info) if !info.contains(CODE_FENCE) => info,
+            _ => {
+                paragraph.push(line);
+                continue;
+            }
+        };
+
+        push_paragraph(&mut blocks, &paragraph);
+        paragraph.clear();
+
+        // an unclosed fence runs to the end of the message
+        let code = lines
+            .by_ref()
+            .take_while(|line| line.trim() != CODE_FENCE)
+            .collect::<Vec<_>>()
+            .join("\n");
+        blocks.push(Block::CodeBlock {
+            language: language(info),
+            code,
+        });
+    }
+    push_paragraph(&mut blocks, &paragraph);
+
+    blocks
+}
+
+pub fn to_html(text: &str) -> String {
+    render(&parse(text))
+}
+
+// the http(s) link at the start of `text` and its length in `text`
+pub fn link_at(text: &str) -> Option<(Url, usize)> {
+    let word = &text[..text.find(char::is_whitespace).unwrap_or(text.len())];
+    let mut link = word.trim_end_matches(LINK_TRAILING_PUNCTUATION);
+    // keep a closing parenthesis that belongs to the link, as in Wikipedia URLs
+    if link.contains('(') && word[link.len()..].starts_with(')') {
+        link = &word[..link.len() + 1];
+    }
+
+    let url = parse_url(link)?;
+
+    Some((url, link.len()))
+}
+
+fn parse_url(link: &str) -> Option<Url> {
+    if !link.starts_with("http://") && !link.starts_with("https://") {
+        return None;
+    }
+
+    Url::parse(link).ok().filter(|url| url.host_str().is_some())
+}
+
+fn push_paragraph(blocks: &mut Vec<Block>, lines: &[&str]) {
+    let text = lines.join("\n");
+    let text = text.trim_matches('\n');
+
+    if !text.trim().is_empty() {
+        blocks.push(Block::Paragraph(parse_inline(text, false)));
+    }
+}
+
+// the first word after the fence, when it looks like a language name
+fn language(info: &str) -> Option<String> {
+    let language = info.split_whitespace().next()?;
+
+    language
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '#' | '.'))
+        .then(|| language.to_ascii_lowercase())
+}
+
+// links cannot nest, so `in_link` turns off link syntax in the text of one
+fn parse_inline(text: &str, in_link: bool) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut plain = String::new();
+    let mut previous = None;
+    let mut i = 0;
+
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        let after_word = previous.is_some_and(char::is_alphanumeric);
+
+        // a backslash makes the punctuation after it plain text
+        if let Some(escaped) = rest
+            .strip_prefix('\\')
+            .and_then(|rest| rest.chars().next())
+            .filter(char::is_ascii_punctuation)
+        {
+            plain.push(escaped);
+            previous = Some(escaped);
+            i += 2;
+            continue;
+        }
+
+        let parsed = match c {
+            '\n' => Some((Inline::LineBreak, 1)),
+            '`' => code_span(rest),
+            '*' if rest.starts_with("**") => delimited(rest, "**")
+                .map(|(inner, len)| (Inline::Bold(parse_inline(inner, in_link)), len)),
+            '*' => delimited(rest, "*")
+                .map(|(inner, len)| (Inline::Italic(parse_inline(inner, in_link)), len)),
+            // snake_case_words are not emphasis
+            '_' if !after_word => delimited(rest, "_")
+                .filter(|(_, len)| !rest[*len..].starts_with(char::is_alphanumeric))
+                .map(|(inner, len)| (Inline::Italic(parse_inline(inner, in_link)), len)),
+            '[' if !in_link => link(rest),
+            'h' if !in_link && !after_word => link_at(rest).map(|(url, len)| {
+                let children = vec![Inline::Text(rest[..len].to_owned())];
+                (
+                    Inline::Link {
+                        url: url.into(),
+                        children,
+                    },
+                    len,
+                )
+            }),
+            // as in `MessageContent::mentions`
+            '@' if !after_word => {
+                let username = &rest[1..];
+                let end = username
+                    .find(|c: char| !c.is_alphanumeric())
+                    .unwrap_or(username.len());
+                (end > 0).then(|| (Inline::Mention(username[..end].to_owned()), end + 1))
+            }
+            _ => None,
+        };
+
+        match parsed {
+            Some((inline, len)) => {
+                if !plain.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                inlines.push(inline);
+                previous = rest[..len].chars().next_back();
+                i += len;
+            }
+            None => {
+                plain.push(c);
+                previous = Some(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        inlines.push(Inline::Text(plain));
+    }
+
+    inlines
+}
+
+// the text between `delimiter` and the next one, which must not be blank or padded
+fn delimited<'a>(text: &'a str, delimiter: &str) -> Option<(&'a str, usize)> {
+    let start = delimiter.len();
+    let end = text[start..].find(delimiter)? + start;
+    let inner = &text[start..end];
+
+    if inner.is_empty()
+        || inner.starts_with(char::is_whitespace)
+        || inner.ends_with(char::is_whitespace)
+    {
+        return None;
+    }
+
+    Some((inner, end + delimiter.len()))
+}
+
+// `code` or ``code with a ` in it``
+fn code_span(text: &str) -> Option<(Inline, usize)> {
+    let ticks = text.len() - text.trim_start_matches('`').len();
+    let delimiter = &text[..ticks];
+    let end = text[ticks..].find(delimiter)? + ticks;
+    let code = &text[ticks..end];
+
+    if code.trim().is_empty() {
+        return None;
+    }
+
+    Some((Inline::Code(code.to_owned()), end + ticks))
+}
+
+// [text](url)
+fn link(text: &str) -> Option<(Inline, usize)> {
+    let close = text.find("](")?;
+    let label = &text[1..close];
+    let target = &text[close + 2..];
+    let end = target.find(')')?;
+    let url = parse_url(&target[..end])?;
+
+    if label.trim().is_empty() || label.contains('\n') {
+        return None;
+    }
+
+    let link = Inline::Link {
+        url: url.into(),
+        children: parse_inline(label, true),
+    };
+
+    Some((link, close + 2 + end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Inline {
+        Inline::Text(value.into())
+    }
+
+    #[test]
+    fn test_parse() {
+        let blocks = parse(
+            "**Hi** @alice, see [the *docs*](https://example.com/docs) or https://example.com.\n\
+                keep snake_case_names, a * b * c, 2*3*4 and \\*stars\\*\n\
+                ```Rust extra\n\
+                fn main() {}\n\
+                ```\n\
+                \n\
+                `a` and ``b`c`` and _done_ [bad](javascript:alert(1))",
+        );
+
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Paragraph(vec![
+                    Inline::Bold(vec![text("Hi")]),
+                    text(" "),
+                    Inline::Mention("alice".into()),
+                    text(", see "),
+                    Inline::Link {
+                        url: "https://example.com/docs".into(),
+                        children: vec![text("the "), Inline::Italic(vec![text("docs")])],
+                    },
+                    text(" or "),
+                    Inline::Link {
+                        url: "https://example.com/".into(),
+                        children: vec![text("https://example.com")],
+                    },
+                    text("."),
+                    Inline::LineBreak,
+                    text("keep snake_case_names, a * b * c, 2"),
+                    Inline::Italic(vec![text("3")]),
+                    text("4 and *stars*"),
+                ]),
+                Block::CodeBlock {
+                    language: Some("rust".into()),
+                    code: "fn main() {}".into(),
+                },
+                Block::Paragraph(vec![
+                    Inline::Code("a".into()),
+                    text(" and "),
+                    Inline::Code("b`c".into()),
+                    text(" and "),
+                    Inline::Italic(vec![text("done")]),
+                    text(" [bad](javascript:alert(1))"),
+                ]),
+            ]
+        );
+
+        assert!(parse(" \n\n ").is_empty());
+        assert_eq!(
+            parse("```\nunclosed *code*"),
+            vec![Block::CodeBlock {
+                language: None,
+                code: "unclosed *code*".into(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::attachment_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/rooms/{id}/attachments")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::post().to(attachment_controller::upload_attachments)),
+    );
+    cfg.service(
+        web::resource("/attachments/{id}")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(attachment_controller::download_attachment)),
+    );
+    cfg.service(
+        web::resource("/attachments/{id}/thumbnails/{size}")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(attachment_controller::download_thumbnail)),
+    );
+    cfg.service(
+        web::resource("/attachments/{id}/signed-url")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::post().to(attachment_controller::sign_attachment_url)),
+    );
+    cfg.service(
+        web::resource("/attachments/{id}/thumbnails/{size}/signed-url")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::post().to(attachment_controller::sign_thumbnail_url)),
+    );
+    // authenticated by the signature in the query
+    cfg.service(
+        web::resource("/files/attachments/{id}")
+            .route(web::get().to(attachment_controller::download_signed_attachment)),
+    );
+    cfg.service(
+        web::resource("/files/attachments/{id}/thumbnails/{size}")
+            .route(web::get().to(attachment_controller::download_signed_thumbnail)),
+    );
+}
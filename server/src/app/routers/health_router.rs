@@ -0,0 +1,12 @@
+use actix_web::web::{self, ServiceConfig};
+
+use crate::app::controllers::health_controller;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/health")
+            .route("/live", web::get().to(health_controller::live))
+            .route("/ready", web::get().to(health_controller::ready)),
+    );
+    cfg.service(web::resource("/api/health").route(web::get().to(health_controller::ready)));
+}
@@ -0,0 +1,47 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::message_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/rooms/{id}/messages")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(message_controller::list_messages))
+            .route(web::post().to(message_controller::send_message)),
+    );
+    cfg.service(
+        web::resource("/messages/{id}")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::patch().to(message_controller::edit_message))
+            .route(web::delete().to(message_controller::delete_message)),
+    );
+    cfg.service(
+        web::resource("/messages/{id}/thread")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(message_controller::get_thread)),
+    );
+    cfg.service(
+        web::resource("/messages/{id}/revisions")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(message_controller::list_revisions)),
+    );
+    cfg.service(
+        web::resource("/me/mentions")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(message_controller::list_mentions)),
+    );
+    cfg.service(
+        web::resource("/messages/{id}/pin")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::put().to(message_controller::pin_message))
+            .route(web::delete().to(message_controller::unpin_message)),
+    );
+    cfg.service(
+        web::resource("/rooms/{id}/pins")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(message_controller::list_pins)),
+    );
+}
@@ -0,0 +1,7 @@
+use actix_web::web::{self, ServiceConfig};
+
+use crate::app::controllers::metrics_controller;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(web::resource("/metrics").route(web::get().to(metrics_controller::metrics)));
+}
@@ -1,2 +1,12 @@
+pub mod attachment_router;
+pub mod health_router;
+pub mod message_router;
+pub mod metrics_router;
+pub mod presence_router;
+pub mod profile_router;
+pub mod reaction_router;
+pub mod room_router;
+pub mod search_router;
 pub mod swagger_router;
 pub mod user_router;
+pub mod ws_router;
@@ -0,0 +1,19 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::presence_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/me/presence")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::put().to(presence_controller::set_presence)),
+    );
+    cfg.service(
+        web::resource("/rooms/{id}/presence")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(presence_controller::list_room_presence)),
+    );
+}
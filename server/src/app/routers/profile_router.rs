@@ -0,0 +1,24 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::profile_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/users/{id}/profile")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(profile_controller::get_profile)),
+    );
+    // avatars are public, so an <img> tag can show them without a token
+    cfg.service(
+        web::resource("/users/{id}/avatar").route(web::get().to(profile_controller::get_avatar)),
+    );
+    cfg.service(
+        web::resource("/me/avatar")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::put().to(profile_controller::set_avatar))
+            .route(web::delete().to(profile_controller::delete_avatar)),
+    );
+}
@@ -0,0 +1,15 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::reaction_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/messages/{id}/reactions/{emoji}")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::put().to(reaction_controller::add_reaction))
+            .route(web::delete().to(reaction_controller::remove_reaction)),
+    );
+}
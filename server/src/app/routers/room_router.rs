@@ -0,0 +1,36 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::room_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/rooms")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(room_controller::list_rooms))
+            .route(web::post().to(room_controller::create_room)),
+    );
+    cfg.service(
+        web::resource("/rooms/{id}/members")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::post().to(room_controller::join_room))
+            .route(web::delete().to(room_controller::leave_room)),
+    );
+    cfg.service(
+        web::resource("/rooms/{id}/invites")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::post().to(room_controller::invite_user)),
+    );
+    cfg.service(
+        web::resource("/rooms/unread")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(room_controller::list_unread)),
+    );
+    cfg.service(
+        web::resource("/rooms/{id}/read")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::post().to(room_controller::mark_read)),
+    );
+}
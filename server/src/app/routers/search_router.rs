@@ -0,0 +1,14 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::search_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/search/messages")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::get().to(search_controller::search_messages)),
+    );
+}
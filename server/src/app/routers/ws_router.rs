@@ -0,0 +1,16 @@
+use actix_web::{
+    middleware::from_fn,
+    web::{self, ServiceConfig},
+};
+
+use crate::app::{controllers::ws_controller, middlewares::jwt};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/ws/tickets")
+            .wrap(from_fn(jwt::verify_jwt))
+            .route(web::post().to(ws_controller::create_ticket)),
+    );
+    // authenticated by the ticket in the query
+    cfg.service(web::resource("/ws").route(web::get().to(ws_controller::connect)));
+}
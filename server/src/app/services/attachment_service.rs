@@ -0,0 +1,739 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use chrono::Duration;
+use sqlx::PgPool;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        extensions::url_signing,
+        media::images,
+        models::{
+            attachments::{
+                AttachmentEntity, AttachmentResponse, SignedUrlQuery, SignedUrlResponse,
+                ThumbnailEntity, UploadedFile, ValidUploadedFile,
+            },
+            events::ServerEvent,
+            messages::MessageResponse,
+        },
+        realtime::hub::ChatHub,
+        repositories::{attachment_repository, discarded_object_repository, message_repository},
+        request_error::{RequestError, RequestResult},
+        services::room_service,
+    },
+    core::{
+        app_config::UploadLimits,
+        storage::{StorageBackend, StorageError},
+    },
+};
+
+const IMAGE_BATCH_SIZE: i64 = 20;
+const DISCARD_BATCH_SIZE: i64 = 100;
+// long enough to load a page of messages, short enough that a leaked URL soon stops working
+const SIGNED_URL_TTL_MINUTES: i64 = 10;
+
+pub async fn upload_attachments(
+    user_id: Uuid,
+    room_id: Uuid,
+    files: Vec<UploadedFile>,
+    limits: &UploadLimits,
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    image_jobs: &Notify,
+) -> RequestResult<Vec<AttachmentResponse>> {
+    room_service::ensure_member(room_id, user_id, pool).await?;
+
+    let files = files
+        .into_iter()
+        .map(|file| file.validate(limits))
+        .collect::<RequestResult<Vec<_>>>()?;
+    if files.is_empty() {
+        return Err(RequestError::BadRequest("No files were uploaded".into()));
+    }
+
+    let keys = files
+        .iter()
+        .map(|_| format!("{room_id}/{}", Uuid::new_v4()))
+        .collect::<Vec<_>>();
+
+    let stored = store(user_id, room_id, &files, &keys, pool, storage).await;
+    if stored.is_err() {
+        discard(&keys, storage).await;
+    }
+    let stored = stored?;
+
+    if stored.iter().any(AttachmentEntity::is_pending_image) {
+        image_jobs.notify_one();
+    }
+
+    Ok(stored.into_iter().map(From::from).collect())
+}
+
+async fn store(
+    user_id: Uuid,
+    room_id: Uuid,
+    files: &[ValidUploadedFile],
+    keys: &[String],
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+) -> RequestResult<Vec<AttachmentEntity>> {
+    for (file, key) in files.iter().zip(keys) {
+        storage
+            .put(key, file.content_type.as_ref(), file.data.clone())
+            .await?;
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut attachments = Vec::with_capacity(files.len());
+    for (file, key) in files.iter().zip(keys) {
+        attachments
+            .push(attachment_repository::create(room_id, user_id, file, key, &mut *tx).await?);
+    }
+    tx.commit().await?;
+
+    Ok(attachments)
+}
+
+// best effort, a failed upload should not leave objects behind
+async fn discard(keys: &[String], storage: &dyn StorageBackend) {
+    for key in keys {
+        if let Err(e) = storage.delete(key).await {
+            tracing::warn!("failed to delete attachment object {}: {}", key, e);
+        }
+    }
+}
+
+pub async fn download_attachment(
+    user_id: Uuid,
+    attachment_id: Uuid,
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+) -> RequestResult<(AttachmentEntity, Bytes)> {
+    let attachment = get_visible(user_id, attachment_id, pool).await?;
+
+    // the original may still carry EXIF metadata such as a GPS position
+    if attachment.is_pending_image() && attachment.uploaded_by != Some(user_id) {
+        return Err(RequestError::Conflict(
+            "Attachment is still being processed".into(),
+        ));
+    }
+
+    let data = storage.get(&attachment.storage_key).await?;
+
+    Ok((attachment, data))
+}
+
+pub async fn download_thumbnail(
+    user_id: Uuid,
+    attachment_id: Uuid,
+    size: &str,
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+) -> RequestResult<(ThumbnailEntity, Bytes)> {
+    get_visible(user_id, attachment_id, pool).await?;
+
+    let thumbnail = attachment_repository::get_thumbnail(attachment_id, size, pool)
+        .await?
+        .ok_or(RequestError::NotFound("Thumbnail not found".into()))?;
+    let data = storage.get(&thumbnail.storage_key).await?;
+
+    Ok((thumbnail, data))
+}
+
+pub async fn sign_attachment_url(
+    user_id: Uuid,
+    attachment_id: Uuid,
+    secret: &str,
+    pool: &PgPool,
+) -> RequestResult<SignedUrlResponse> {
+    get_visible(user_id, attachment_id, pool).await?;
+
+    Ok(sign(&attachment_file_path(attachment_id), user_id, secret))
+}
+
+pub async fn sign_thumbnail_url(
+    user_id: Uuid,
+    attachment_id: Uuid,
+    size: &str,
+    secret: &str,
+    pool: &PgPool,
+) -> RequestResult<SignedUrlResponse> {
+    get_visible(user_id, attachment_id, pool).await?;
+    attachment_repository::get_thumbnail(attachment_id, size, pool)
+        .await?
+        .ok_or(RequestError::NotFound("Thumbnail not found".into()))?;
+
+    Ok(sign(
+        &thumbnail_file_path(attachment_id, size),
+        user_id,
+        secret,
+    ))
+}
+
+// the user the URL was signed for, who must still be able to see the attachment
+pub fn verify_attachment_url(
+    attachment_id: Uuid,
+    query: &SignedUrlQuery,
+    secret: &str,
+) -> RequestResult<Uuid> {
+    let path = attachment_file_path(attachment_id);
+    url_signing::verify_url(&path, query.user, query.expires, &query.signature, secret)?;
+
+    Ok(query.user)
+}
+
+pub fn verify_thumbnail_url(
+    attachment_id: Uuid,
+    size: &str,
+    query: &SignedUrlQuery,
+    secret: &str,
+) -> RequestResult<Uuid> {
+    let path = thumbnail_file_path(attachment_id, size);
+    url_signing::verify_url(&path, query.user, query.expires, &query.signature, secret)?;
+
+    Ok(query.user)
+}
+
+fn attachment_file_path(attachment_id: Uuid) -> String {
+    format!("/files/attachments/{attachment_id}")
+}
+
+fn thumbnail_file_path(attachment_id: Uuid, size: &str) -> String {
+    format!("/files/attachments/{attachment_id}/thumbnails/{size}")
+}
+
+fn sign(path: &str, user_id: Uuid, secret: &str) -> SignedUrlResponse {
+    let ttl = Duration::minutes(SIGNED_URL_TTL_MINUTES);
+    let (url, expires_at) = url_signing::sign_url(path, user_id, ttl, secret);
+
+    SignedUrlResponse { url, expires_at }
+}
+
+// unsent uploads are only visible to their uploader and attachments of deleted
+// messages to nobody
+async fn get_visible(
+    user_id: Uuid,
+    attachment_id: Uuid,
+    pool: &PgPool,
+) -> RequestResult<AttachmentEntity> {
+    let not_found = || RequestError::NotFound("Attachment not found".into());
+
+    let attachment = attachment_repository::get(attachment_id, pool).await?;
+    room_service::ensure_member(attachment.room_id, user_id, pool).await?;
+
+    match attachment.message_id {
+        Some(message_id) => {
+            let message = message_repository::get(message_id, pool).await?;
+            if message.deleted_at.is_some() {
+                return Err(not_found());
+            }
+        }
+        None if attachment.uploaded_by != Some(user_id) => return Err(not_found()),
+        None => {}
+    }
+
+    Ok(attachment)
+}
+
+// run by the image worker, returns how many images it went through
+pub async fn process_pending_images(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    hub: &ChatHub,
+) -> RequestResult<usize> {
+    let mut processed = 0;
+
+    loop {
+        let pending = attachment_repository::list_unprocessed(IMAGE_BATCH_SIZE, pool).await?;
+        if pending.is_empty() {
+            return Ok(processed);
+        }
+
+        for attachment in pending {
+            process_image(attachment, pool, storage, hub).await?;
+            processed += 1;
+        }
+    }
+}
+
+async fn process_image(
+    attachment: AttachmentEntity,
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+    hub: &ChatHub,
+) -> RequestResult<()> {
+    let data = match storage.get(&attachment.storage_key).await {
+        Ok(data) => data,
+        Err(e @ StorageError::NotFound(_)) => return skip_image(attachment, e, pool, hub).await,
+        Err(e) => return Err(e.into()),
+    };
+    let processed = tokio::task::spawn_blocking(move || images::process(&data))
+        .await
+        .map_err(|e| RequestError::InternalServerError(e.to_string()))?;
+
+    let processed = match processed {
+        Ok(processed) => processed,
+        Err(e) => return skip_image(attachment, e, pool, hub).await,
+    };
+
+    let size_bytes = match processed.stripped {
+        Some(stripped) => {
+            let size_bytes = stripped.len() as i64;
+            storage
+                .put(&attachment.storage_key, &attachment.content_type, stripped)
+                .await?;
+            size_bytes
+        }
+        None => attachment.size_bytes,
+    };
+
+    // deterministic keys, so a retry overwrites instead of leaving objects behind
+    let keys = processed
+        .thumbnails
+        .iter()
+        .map(|thumbnail| format!("{}.{}", attachment.storage_key, thumbnail.size))
+        .collect::<Vec<_>>();
+    for (thumbnail, key) in processed.thumbnails.iter().zip(&keys) {
+        storage
+            .put(key, thumbnail.content_type, thumbnail.data.clone())
+            .await?;
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut thumbnails = Vec::with_capacity(keys.len());
+    for (thumbnail, key) in processed.thumbnails.iter().zip(&keys) {
+        thumbnails.push(
+            attachment_repository::create_thumbnail(attachment.id, thumbnail, key, &mut *tx)
+                .await?,
+        );
+    }
+    let attachment = attachment_repository::mark_processed(
+        attachment.id,
+        Some((processed.width, processed.height)),
+        processed.blurhash.as_deref(),
+        size_bytes,
+        &mut *tx,
+    )
+    .await?;
+    tx.commit().await?;
+
+    notify_processed(attachment, thumbnails, hub);
+
+    Ok(())
+}
+
+// not retried, the attachment just has no preview
+async fn skip_image(
+    attachment: AttachmentEntity,
+    reason: impl std::fmt::Display,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<()> {
+    tracing::warn!(
+        "cannot process image attachment {}: {}",
+        attachment.id,
+        reason
+    );
+
+    let attachment = attachment_repository::mark_processed(
+        attachment.id,
+        None,
+        None,
+        attachment.size_bytes,
+        pool,
+    )
+    .await?;
+    notify_processed(attachment, Vec::new(), hub);
+
+    Ok(())
+}
+
+// the room sees attachments of sent messages, an unsent upload only concerns its uploader
+fn notify_processed(attachment: AttachmentEntity, thumbnails: Vec<ThumbnailEntity>, hub: &ChatHub) {
+    let room_id = attachment.room_id;
+    let (message_id, uploaded_by) = (attachment.message_id, attachment.uploaded_by);
+    let event = ServerEvent::AttachmentUpdated(AttachmentResponse::new(attachment, thumbnails));
+
+    match (message_id, uploaded_by) {
+        (Some(_), _) => hub.broadcast(room_id, &event),
+        (None, Some(uploaded_by)) => hub.send_to_user(uploaded_by, &event),
+        (None, None) => {}
+    }
+}
+
+// run by the purge job, returns how many objects it deleted; an object that could not
+// be deleted stays queued for the next run
+pub async fn delete_discarded_objects(
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+) -> RequestResult<usize> {
+    let mut deleted = 0;
+
+    loop {
+        let keys = discarded_object_repository::list(DISCARD_BATCH_SIZE, pool).await?;
+
+        let mut done = Vec::with_capacity(keys.len());
+        for key in keys {
+            match storage.delete(&key).await {
+                Ok(()) => done.push(key),
+                Err(e) => tracing::warn!("failed to delete discarded object {}: {}", key, e),
+            }
+        }
+        if done.is_empty() {
+            return Ok(deleted);
+        }
+
+        discarded_object_repository::forget(&done, pool).await?;
+        deleted += done.len();
+    }
+}
+
+// fills in the attachments of a page of messages, thumbnails included
+pub async fn attach_attachments(
+    messages: &mut [MessageResponse],
+    pool: &PgPool,
+) -> RequestResult<()> {
+    let message_ids = messages.iter().map(|m| m.id).collect::<Vec<_>>();
+    let found = attachment_repository::list_for_messages(&message_ids, pool).await?;
+
+    let attachment_ids = found.iter().map(|a| a.id).collect::<Vec<_>>();
+    let mut thumbnails = HashMap::<Uuid, Vec<ThumbnailEntity>>::new();
+    for thumbnail in attachment_repository::list_thumbnails(&attachment_ids, pool).await? {
+        thumbnails
+            .entry(thumbnail.attachment_id)
+            .or_default()
+            .push(thumbnail);
+    }
+
+    let mut attachments = HashMap::<Uuid, Vec<AttachmentResponse>>::new();
+    for attachment in found {
+        if let Some(message_id) = attachment.message_id {
+            let thumbnails = thumbnails.remove(&attachment.id).unwrap_or_default();
+            attachments
+                .entry(message_id)
+                .or_default()
+                .push(AttachmentResponse::new(attachment, thumbnails));
+        }
+    }
+
+    for message in messages {
+        message.attachments = attachments.remove(&message.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{mime, web};
+    use expect_test::expect;
+
+    use super::*;
+    use crate::{
+        app::{
+            models::{
+                messages::{
+                    ValidCreateMessageRequest, ValidMessageHistoryQuery, domain::MessageContent,
+                },
+                rooms::domain::RoomRole,
+            },
+            realtime::hub::ChatHub,
+            repositories::{room_repository, user_repository},
+            services::message_service,
+        },
+        core::storage::LocalStorage,
+    };
+
+    fn file(name: &str, content_type: mime::Mime, data: &'static [u8]) -> UploadedFile {
+        UploadedFile {
+            filename: name.into(),
+            content_type: Some(content_type),
+            data: Bytes::from_static(data),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_attachments(pool: PgPool) {
+        let hub = ChatHub::default();
+        let link_jobs = Notify::new();
+        let root = std::env::temp_dir().join(format!("web_chat_{}", Uuid::new_v4()));
+        let storage = LocalStorage::new(&root);
+        let limits = UploadLimits::new(16, vec![mime::IMAGE_STAR, mime::TEXT_PLAIN]);
+
+        let author = user_repository::create("author@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let other = user_repository::create("other@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let outsider = user_repository::create("outsider@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, author, &pool)
+            .await
+            .unwrap();
+        for (user_id, role) in [(author, RoomRole::Owner), (other, RoomRole::Member)] {
+            room_repository::add_member(room.id, user_id, role, &pool)
+                .await
+                .unwrap();
+        }
+
+        let image_jobs = Notify::new();
+        let upload = |files| {
+            upload_attachments(
+                author,
+                room.id,
+                files,
+                &limits,
+                &pool,
+                &storage,
+                &image_jobs,
+            )
+        };
+
+        let error = upload(vec![file("big.png", mime::IMAGE_PNG, &[0; 17])])
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["413 Payload Too Large. Context: Files may not be larger than 16 bytes"];
+        exp.assert_eq(&error.to_string());
+
+        let error = upload(vec![file("page.html", mime::TEXT_HTML, b"<script>")])
+            .await
+            .err()
+            .unwrap();
+        let exp = expect![
+            "415 Unsupported Media Type. Context: Files of type text/html are not accepted"
+        ];
+        exp.assert_eq(&error.to_string());
+
+        let uploaded = upload(vec![file("C:\\notes.txt", mime::TEXT_PLAIN, b"hello")])
+            .await
+            .unwrap();
+        let attachment_id = uploaded[0].id;
+        assert_eq!(uploaded[0].filename, "notes.txt");
+
+        // not sent yet, so only the uploader sees it
+        let error = download_attachment(other, attachment_id, &pool, &storage)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["404 Not Found. Context: Attachment not found"];
+        exp.assert_eq(&error.to_string());
+
+        let message = |attachment_ids| ValidCreateMessageRequest {
+            content: MessageContent::caption(String::new()).unwrap(),
+            parent_id: None,
+            attachment_ids,
+        };
+        let error = message_service::send_message(
+            other,
+            room.id,
+            message(vec![attachment_id]),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .err()
+        .unwrap();
+        let exp = expect![
+            "400 Bad Request. Context: Attachments must be your own unsent uploads to this room"
+        ];
+        exp.assert_eq(&error.to_string());
+
+        let sent = message_service::send_message(
+            author,
+            room.id,
+            message(vec![attachment_id]),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .unwrap();
+        assert_eq!(sent.attachments[0].message_id, Some(sent.id));
+
+        let (_, data) = download_attachment(other, attachment_id, &pool, &storage)
+            .await
+            .unwrap();
+        assert_eq!(data, "hello");
+        let error = download_attachment(outsider, attachment_id, &pool, &storage)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, RequestError::Forbidden(_)));
+
+        let query = ValidMessageHistoryQuery {
+            before: None,
+            limit: None.try_into().unwrap(),
+        };
+        let history = message_service::list_messages(other, room.id, query, &pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            history[0].attachments[0].url,
+            format!("/attachments/{attachment_id}")
+        );
+
+        // a signed URL names its user, whose access is checked again when it is used
+        let error = sign_attachment_url(outsider, attachment_id, "secret", &pool)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, RequestError::Forbidden(_)));
+        let signed = sign_attachment_url(other, attachment_id, "secret", &pool)
+            .await
+            .unwrap();
+        let query = signed
+            .url
+            .strip_prefix(&format!("/files/attachments/{attachment_id}?"))
+            .map(|query| web::Query::<SignedUrlQuery>::from_query(query).unwrap())
+            .unwrap();
+        let user_id = verify_attachment_url(attachment_id, &query, "secret").unwrap();
+        assert_eq!(user_id, other);
+        assert!(verify_attachment_url(uploaded[0].id, &query, "other").is_err());
+        assert!(verify_thumbnail_url(attachment_id, "small", &query, "secret").is_err());
+        room_repository::remove_member(room.id, other, &pool)
+            .await
+            .unwrap();
+        let error = download_attachment(user_id, attachment_id, &pool, &storage)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, RequestError::Forbidden(_)));
+
+        // purging the deleted message takes the stored file with it
+        let storage_key = attachment_repository::get(attachment_id, &pool)
+            .await
+            .unwrap()
+            .storage_key;
+        message_service::delete_message(author, sent.id, &pool, &hub)
+            .await
+            .unwrap();
+        message_service::purge_deleted(chrono::Duration::zero(), &pool)
+            .await
+            .unwrap();
+        assert_eq!(delete_discarded_objects(&pool, &storage).await.unwrap(), 1);
+        assert!(matches!(
+            storage.get(&storage_key).await,
+            Err(StorageError::NotFound(_))
+        ));
+        let error = download_attachment(author, attachment_id, &pool, &storage)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, RequestError::NotFound(_)));
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_image_processing(pool: PgPool) {
+        let hub = ChatHub::default();
+        let link_jobs = Notify::new();
+        let root = std::env::temp_dir().join(format!("web_chat_{}", Uuid::new_v4()));
+        let storage = LocalStorage::new(&root);
+        let image_jobs = Notify::new();
+
+        let author = user_repository::create("author@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let other = user_repository::create("other@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, author, &pool)
+            .await
+            .unwrap();
+        for (user_id, role) in [(author, RoomRole::Owner), (other, RoomRole::Member)] {
+            room_repository::add_member(room.id, user_id, role, &pool)
+                .await
+                .unwrap();
+        }
+
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(640, 480, image::Rgba([255, 0, 0, 128]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let files = vec![
+            UploadedFile {
+                filename: "red.png".into(),
+                content_type: Some(mime::IMAGE_PNG),
+                data: png.into(),
+            },
+            file("broken.png", mime::IMAGE_PNG, b"not a png"),
+        ];
+        let uploaded = upload_attachments(
+            author,
+            room.id,
+            files,
+            &UploadLimits::default(),
+            &pool,
+            &storage,
+            &image_jobs,
+        )
+        .await
+        .unwrap();
+        let ids = uploaded.iter().map(|a| a.id).collect::<Vec<_>>();
+
+        let message = ValidCreateMessageRequest {
+            content: MessageContent::caption(String::new()).unwrap(),
+            parent_id: None,
+            attachment_ids: ids.clone(),
+        };
+        message_service::send_message(author, room.id, message, &pool, &hub, &link_jobs)
+            .await
+            .unwrap();
+
+        let error = download_attachment(other, ids[0], &pool, &storage)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["409 Conflict. Context: Attachment is still being processed"];
+        exp.assert_eq(&error.to_string());
+
+        let (_, mut rx) = hub.register(other, vec![room.id]);
+        let processed = process_pending_images(&pool, &storage, &hub).await.unwrap();
+        assert_eq!(processed, 2);
+        assert!(rx.try_recv().is_ok());
+
+        let query = ValidMessageHistoryQuery {
+            before: None,
+            limit: None.try_into().unwrap(),
+        };
+        let history = message_service::list_messages(other, room.id, query, &pool)
+            .await
+            .unwrap();
+        let summary = history[0]
+            .attachments
+            .iter()
+            .map(|a| {
+                let thumbnails = a
+                    .thumbnails
+                    .iter()
+                    .map(|t| format!(" {} {}x{} {}", t.size, t.width, t.height, t.content_type));
+                let dimensions = a.width.zip(a.height).map(|(w, h)| format!(" {w}x{h}"));
+                format!("{}{}", a.filename, dimensions.unwrap_or_default())
+                    + &thumbnails.collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        let exp = expect![[r#"
+            [
+                "red.png 640x480 small 320x240 image/png",
+                "broken.png",
+            ]"#]];
+        exp.assert_eq(&format!("{:#?}", summary));
+
+        let (_, data) = download_thumbnail(other, ids[0], "small", &pool, &storage)
+            .await
+            .unwrap();
+        assert_eq!(image::load_from_memory(&data).unwrap().width(), 320);
+        download_attachment(other, ids[0], &pool, &storage)
+            .await
+            .unwrap();
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
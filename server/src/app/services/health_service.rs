@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashSet};
+
+use sqlx::{PgPool, migrate::Migrate};
+
+use crate::{
+    app::{
+        models::health::{ComponentHealth, HealthResponse},
+        realtime::hub::ChatHub,
+    },
+    core::database::MIGRATOR,
+};
+
+// errors are logged, not exposed: readiness is served without auth
+const UNAVAILABLE: &str = "unavailable";
+
+pub fn check_liveness() -> HealthResponse {
+    HealthResponse::from_components(BTreeMap::new())
+}
+
+pub async fn check_readiness(pool: &PgPool, hub: &ChatHub) -> HealthResponse {
+    let mut components = BTreeMap::new();
+
+    components.insert("database", check_database(pool).await);
+    components.insert("migrations", check_migrations(pool).await);
+    components.insert("realtime", check_realtime(hub));
+
+    HealthResponse::from_components(components)
+}
+
+async fn check_database(pool: &PgPool) -> ComponentHealth {
+    match sqlx::query("SELECT 1").execute(pool).await {
+        Ok(_) => ComponentHealth::up(),
+        Err(e) => unavailable("database", e),
+    }
+}
+
+async fn check_migrations(pool: &PgPool) -> ComponentHealth {
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => return unavailable("migrations", e),
+    };
+
+    let applied = match conn.list_applied_migrations().await {
+        Ok(applied) => applied
+            .into_iter()
+            .map(|m| m.version)
+            .collect::<HashSet<_>>(),
+        Err(e) => return unavailable("migrations", e),
+    };
+
+    let pending = MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration() && !applied.contains(&m.version))
+        .count();
+
+    match pending {
+        0 => ComponentHealth::up(),
+        n => ComponentHealth::down(format!("{n} pending migration(s)")),
+    }
+}
+
+fn unavailable(component: &str, error: impl std::fmt::Display) -> ComponentHealth {
+    tracing::error!("Readiness check of {} failed: {}", component, error);
+    ComponentHealth::down(UNAVAILABLE)
+}
+
+// a draining instance reports down so load balancers stop sending new sessions to it
+fn check_realtime(hub: &ChatHub) -> ComponentHealth {
+    match hub.is_closing() {
+        true => ComponentHealth::down("shutting down"),
+        false => ComponentHealth::up(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use sqlx::PgPool;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_check_readiness(pool: PgPool) {
+        let response = check_readiness(&pool, &ChatHub::default()).await;
+
+        let exp = expect![[
+            r#"{"status":"up","components":{"database":{"status":"up"},"migrations":{"status":"up"},"realtime":{"status":"up"}}}"#
+        ]];
+        exp.assert_eq(&serde_json::to_string(&response).unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_check_readiness_closed_pool(pool: PgPool) {
+        pool.close().await;
+        let response = check_readiness(&pool, &ChatHub::default()).await;
+
+        let exp = expect![[
+            r#"{"status":"down","components":{"database":{"status":"down","details":"unavailable"},"migrations":{"status":"down","details":"unavailable"},"realtime":{"status":"up"}}}"#
+        ]];
+        exp.assert_eq(&serde_json::to_string(&response).unwrap());
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use futures_util::future::join_all;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        models::{
+            events::ServerEvent, link_previews::LinkPreviewResponse, messages::MessageResponse,
+        },
+        realtime::hub::ChatHub,
+        repositories::{link_preview_repository, message_repository},
+        request_error::RequestResult,
+        services::attachment_service,
+    },
+    core::unfurl::{LinkMetadata, Unfurler},
+};
+
+// fetched concurrently, each fetch is bounded by the unfurler's timeout
+const LINK_BATCH_SIZE: i64 = 20;
+// a preview older than this is fetched again when a new message links it
+const CACHE_TTL_HOURS: i64 = 24;
+
+// called in the transaction that stores or edits the message, returns whether the
+// worker has anything new to fetch
+pub async fn record_links(
+    message_id: Uuid,
+    links: &[String],
+    conn: &mut PgConnection,
+) -> RequestResult<bool> {
+    link_preview_repository::retain(message_id, links, &mut *conn).await?;
+    if links.is_empty() {
+        return Ok(false);
+    }
+
+    let stale_before = Utc::now() - Duration::hours(CACHE_TTL_HOURS);
+    let queued = link_preview_repository::enqueue(links, stale_before, &mut *conn).await?;
+    link_preview_repository::link(message_id, links, &mut *conn).await?;
+
+    Ok(queued > 0)
+}
+
+// run by the link preview worker, returns how many links it went through
+pub async fn process_pending_links(
+    pool: &PgPool,
+    unfurler: &Unfurler,
+    hub: &ChatHub,
+) -> RequestResult<usize> {
+    let mut processed = 0;
+
+    loop {
+        let pending = link_preview_repository::list_pending(LINK_BATCH_SIZE, pool).await?;
+        if pending.is_empty() {
+            return Ok(processed);
+        }
+
+        let fetched = join_all(
+            pending
+                .iter()
+                .map(|url| fetch_link(url, pool, unfurler, hub)),
+        );
+        for result in fetched.await {
+            result?;
+            processed += 1;
+        }
+    }
+}
+
+// a link that cannot be previewed is not retried until the cache expires
+async fn fetch_link(
+    url: &str,
+    pool: &PgPool,
+    unfurler: &Unfurler,
+    hub: &ChatHub,
+) -> RequestResult<()> {
+    let metadata = match unfurler.unfurl(url).await {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            tracing::info!("no preview for {}: {}", url, e);
+            LinkMetadata::default()
+        }
+    };
+    link_preview_repository::save(url, &metadata, pool).await?;
+
+    if metadata.is_empty() {
+        return Ok(());
+    }
+
+    // older messages already showed the cached preview
+    let since = Utc::now() - Duration::hours(CACHE_TTL_HOURS);
+    for message_id in link_preview_repository::list_linking_messages(url, since, pool).await? {
+        let mut message = MessageResponse::from(message_repository::get(message_id, pool).await?);
+        attachment_service::attach_attachments(std::slice::from_mut(&mut message), pool).await?;
+        attach_link_previews(std::slice::from_mut(&mut message), pool).await?;
+
+        hub.broadcast(message.room_id, &ServerEvent::MessageUpdated(message));
+    }
+
+    Ok(())
+}
+
+// fills in the link previews of a page of messages
+pub async fn attach_link_previews(
+    messages: &mut [MessageResponse],
+    pool: &PgPool,
+) -> RequestResult<()> {
+    let message_ids = messages.iter().map(|m| m.id).collect::<Vec<_>>();
+    let found = link_preview_repository::list_for_messages(&message_ids, pool).await?;
+
+    let mut previews = HashMap::<Uuid, Vec<LinkPreviewResponse>>::new();
+    for preview in found {
+        previews
+            .entry(preview.message_id)
+            .or_default()
+            .push(preview.into());
+    }
+
+    for message in messages {
+        message.link_previews = previews.remove(&message.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use actix_web::{App, HttpResponse, HttpServer, web};
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::app::{
+        models::{
+            messages::{
+                ValidCreateMessageRequest, ValidEditMessageRequest, ValidMessageHistoryQuery,
+            },
+            rooms::domain::RoomRole,
+        },
+        realtime::hub::SessionCommand,
+        repositories::{room_repository, user_repository},
+        services::message_service,
+    };
+
+    async fn page(path: web::Path<String>) -> HttpResponse {
+        match path.as_str() {
+            "article" => HttpResponse::Ok()
+                .content_type("text/html")
+                .body(r#"<meta property="og:title" content="An article">"#),
+            _ => HttpResponse::NotFound().finish(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_link_previews(pool: PgPool) {
+        let lst = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = lst.local_addr().unwrap();
+        let server = HttpServer::new(|| App::new().route("/{path}", web::get().to(page)))
+            .workers(1)
+            .listen(lst)
+            .unwrap()
+            .run();
+        let handle = server.handle();
+        tokio::spawn(server);
+
+        let hub = ChatHub::default();
+        let link_jobs = Notify::new();
+        let unfurler = Unfurler::default().with_private_networks(true);
+
+        let author = user_repository::create("author@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, author, &pool)
+            .await
+            .unwrap();
+        room_repository::add_member(room.id, author, RoomRole::Owner, &pool)
+            .await
+            .unwrap();
+
+        let article = format!("http://{addr}/article");
+        let message = ValidCreateMessageRequest {
+            content: format!("read {article} and http://{addr}/missing")
+                .try_into()
+                .unwrap(),
+            parent_id: None,
+            attachment_ids: Vec::new(),
+        };
+        let sent = message_service::send_message(author, room.id, message, &pool, &hub, &link_jobs)
+            .await
+            .unwrap();
+        assert!(sent.link_previews.is_empty());
+
+        let (_, mut rx) = hub.register(author, vec![room.id]);
+        let processed = process_pending_links(&pool, &unfurler, &hub).await.unwrap();
+        assert_eq!(processed, 2);
+        let Ok(SessionCommand::Send(event)) = rx.try_recv() else {
+            panic!("expected the updated message");
+        };
+        let event = serde_json::from_str::<serde_json::Value>(&event).unwrap();
+        assert_eq!(event["type"], "message_updated");
+        assert_eq!(event["payload"]["link_previews"][0]["title"], "An article");
+        assert!(rx.try_recv().is_err());
+
+        // cached, a second message linking the article does not fetch it again
+        let message = ValidCreateMessageRequest {
+            content: format!("again {article}").try_into().unwrap(),
+            parent_id: None,
+            attachment_ids: Vec::new(),
+        };
+        message_service::send_message(author, room.id, message, &pool, &hub, &link_jobs)
+            .await
+            .unwrap();
+        assert_eq!(
+            process_pending_links(&pool, &unfurler, &hub).await.unwrap(),
+            0
+        );
+
+        let query = ValidMessageHistoryQuery {
+            before: None,
+            limit: None.try_into().unwrap(),
+        };
+        let history = message_service::list_messages(author, room.id, query, &pool)
+            .await
+            .unwrap();
+        assert!(history.iter().all(|m| m.link_previews.len() == 1));
+
+        // editing the link away drops its preview
+        let edit = ValidEditMessageRequest {
+            content: "nothing to see".to_string().try_into().unwrap(),
+        };
+        let edited = message_service::edit_message(author, sent.id, edit, &pool, &hub, &link_jobs)
+            .await
+            .unwrap();
+        assert!(edited.link_previews.is_empty());
+
+        handle.stop(false).await;
+    }
+}
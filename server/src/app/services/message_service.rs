@@ -0,0 +1,786 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::app::{
+    models::{
+        events::ServerEvent,
+        messages::{
+            MessageEntity, MessageResponse, MessageRevisionResponse, PinnedMessageResponse,
+            ThreadResponse, ValidCreateMessageRequest, ValidEditMessageRequest,
+            ValidMessageHistoryQuery,
+        },
+    },
+    realtime::hub::ChatHub,
+    repositories::{
+        attachment_repository, mention_repository, message_repository, pin_repository,
+        role_repository, room_repository,
+    },
+    request_error::{RequestError, RequestResult},
+    services::{attachment_service, link_preview_service, reaction_service, room_service},
+};
+
+const MODERATOR_ROLES: [&str; 2] = ["admin", "moderator"];
+
+pub async fn send_message(
+    user_id: Uuid,
+    room_id: Uuid,
+    message: ValidCreateMessageRequest,
+    pool: &PgPool,
+    hub: &ChatHub,
+    link_jobs: &Notify,
+) -> RequestResult<MessageResponse> {
+    let mut tx = pool.begin().await?;
+    room_service::ensure_member(room_id, user_id, &mut *tx).await?;
+
+    if let Some(parent_id) = message.parent_id {
+        let parent = message_repository::get_for_update(parent_id, &mut *tx).await?;
+        ensure_thread_parent(&parent, room_id)?;
+    }
+
+    let created = message_repository::create(
+        room_id,
+        user_id,
+        message.content.as_ref(),
+        &message.content.to_html(),
+        message.parent_id,
+        &mut *tx,
+    )
+    .await?;
+    let mentioned =
+        mention_repository::create(created.id, &message.content.mentions(), &mut *tx).await?;
+    let links_queued =
+        link_preview_service::record_links(created.id, &message.content.links(), &mut tx).await?;
+    let attachments = attachment_repository::link(
+        created.id,
+        room_id,
+        user_id,
+        &message.attachment_ids,
+        &mut *tx,
+    )
+    .await?;
+    if attachments.len() != message.attachment_ids.len() {
+        return Err(RequestError::BadRequest(
+            "Attachments must be your own unsent uploads to this room".into(),
+        ));
+    }
+
+    let parent = match created.parent_id {
+        Some(parent_id) => {
+            Some(message_repository::record_reply(parent_id, created.created_at, &mut *tx).await?)
+        }
+        None => None,
+    };
+    tx.commit().await?;
+
+    if links_queued {
+        link_jobs.notify_one();
+    }
+
+    let mut created = MessageResponse::from(created);
+    // images uploaded a moment earlier may already have their thumbnails, and cached
+    // links their previews
+    if !attachments.is_empty() {
+        attachment_service::attach_attachments(std::slice::from_mut(&mut created), pool).await?;
+    }
+    link_preview_service::attach_link_previews(std::slice::from_mut(&mut created), pool).await?;
+    match parent {
+        Some(parent) => {
+            hub.broadcast(room_id, &ServerEvent::ThreadReplyCreated(created.clone()));
+            hub.broadcast(room_id, &ServerEvent::ThreadUpdated(parent.into()));
+        }
+        None => hub.broadcast(room_id, &ServerEvent::MessageCreated(created.clone())),
+    }
+    notify_mentioned(&mentioned, &created, hub);
+
+    Ok(created)
+}
+
+// threads are one level deep and live in the room of their parent
+fn ensure_thread_parent(parent: &MessageEntity, room_id: Uuid) -> RequestResult<()> {
+    if parent.room_id != room_id {
+        return Err(RequestError::BadRequest(
+            "Parent message belongs to another room".into(),
+        ));
+    }
+    if parent.parent_id.is_some() {
+        return Err(RequestError::BadRequest(
+            "Replies cannot have replies".into(),
+        ));
+    }
+    if parent.deleted_at.is_some() {
+        return Err(RequestError::Conflict(
+            "Cannot reply to a deleted message".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn list_messages(
+    user_id: Uuid,
+    room_id: Uuid,
+    query: ValidMessageHistoryQuery,
+    pool: &PgPool,
+) -> RequestResult<Vec<MessageResponse>> {
+    room_service::ensure_member(room_id, user_id, pool).await?;
+
+    let messages =
+        message_repository::list(room_id, query.before, query.limit.into(), pool).await?;
+    let mut messages = messages
+        .into_iter()
+        .map(MessageResponse::from)
+        .collect::<Vec<_>>();
+    reaction_service::attach_reactions(user_id, &mut messages, pool).await?;
+    attachment_service::attach_attachments(&mut messages, pool).await?;
+    link_preview_service::attach_link_previews(&mut messages, pool).await?;
+
+    Ok(messages)
+}
+
+pub async fn get_thread(
+    user_id: Uuid,
+    message_id: Uuid,
+    query: ValidMessageHistoryQuery,
+    pool: &PgPool,
+) -> RequestResult<ThreadResponse> {
+    let parent = message_repository::get(message_id, pool).await?;
+    room_service::ensure_member(parent.room_id, user_id, pool).await?;
+
+    if parent.parent_id.is_some() {
+        return Err(RequestError::BadRequest(
+            "Message is a reply, not a thread".into(),
+        ));
+    }
+
+    let replies =
+        message_repository::list_replies(message_id, query.before, query.limit.into(), pool)
+            .await?;
+
+    let mut messages = std::iter::once(parent)
+        .chain(replies)
+        .map(MessageResponse::from)
+        .collect::<Vec<_>>();
+    reaction_service::attach_reactions(user_id, &mut messages, pool).await?;
+    attachment_service::attach_attachments(&mut messages, pool).await?;
+    link_preview_service::attach_link_previews(&mut messages, pool).await?;
+
+    let replies = messages.split_off(1);
+    let parent = messages.remove(0);
+
+    Ok(ThreadResponse { parent, replies })
+}
+
+pub async fn list_mentions(
+    user_id: Uuid,
+    query: ValidMessageHistoryQuery,
+    pool: &PgPool,
+) -> RequestResult<Vec<MessageResponse>> {
+    let messages =
+        mention_repository::list_for_user(user_id, query.before, query.limit.into(), pool).await?;
+    let mut messages = messages
+        .into_iter()
+        .map(MessageResponse::from)
+        .collect::<Vec<_>>();
+    reaction_service::attach_reactions(user_id, &mut messages, pool).await?;
+    attachment_service::attach_attachments(&mut messages, pool).await?;
+    link_preview_service::attach_link_previews(&mut messages, pool).await?;
+
+    Ok(messages)
+}
+
+// only users that were not mentioned by an earlier version of the message are notified
+fn notify_mentioned(user_ids: &[Uuid], message: &MessageResponse, hub: &ChatHub) {
+    if user_ids.is_empty() {
+        return;
+    }
+
+    let event = ServerEvent::Mentioned(message.clone());
+    for user_id in user_ids {
+        hub.send_to_user(*user_id, &event);
+    }
+}
+
+pub async fn edit_message(
+    user_id: Uuid,
+    message_id: Uuid,
+    message: ValidEditMessageRequest,
+    pool: &PgPool,
+    hub: &ChatHub,
+    link_jobs: &Notify,
+) -> RequestResult<MessageResponse> {
+    let mut tx = pool.begin().await?;
+
+    let current = message_repository::get_for_update(message_id, &mut *tx).await?;
+    if current.user_id != user_id {
+        return Err(RequestError::Forbidden(
+            "Only the author can edit a message".into(),
+        ));
+    }
+    if current.deleted_at.is_some() {
+        return Err(RequestError::Conflict(
+            "Deleted messages cannot be edited".into(),
+        ));
+    }
+    room_service::ensure_member(current.room_id, user_id, &mut *tx).await?;
+
+    if current.content == message.content.as_ref() {
+        return Ok(current.into());
+    }
+
+    message_repository::create_revision(message_id, &current.content, user_id, &mut *tx).await?;
+    let edited = message_repository::update_content(
+        message_id,
+        message.content.as_ref(),
+        &message.content.to_html(),
+        &mut *tx,
+    )
+    .await?;
+
+    let mentions = message.content.mentions();
+    mention_repository::retain(message_id, &mentions, &mut *tx).await?;
+    let mentioned = mention_repository::create(message_id, &mentions, &mut *tx).await?;
+    let links_queued =
+        link_preview_service::record_links(message_id, &message.content.links(), &mut tx).await?;
+    tx.commit().await?;
+
+    if links_queued {
+        link_jobs.notify_one();
+    }
+
+    let mut edited = MessageResponse::from(edited);
+    attachment_service::attach_attachments(std::slice::from_mut(&mut edited), pool).await?;
+    link_preview_service::attach_link_previews(std::slice::from_mut(&mut edited), pool).await?;
+    hub.broadcast(edited.room_id, &ServerEvent::MessageEdited(edited.clone()));
+    notify_mentioned(&mentioned, &edited, hub);
+
+    Ok(edited)
+}
+
+pub async fn delete_message(
+    user_id: Uuid,
+    message_id: Uuid,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<MessageResponse> {
+    let mut tx = pool.begin().await?;
+
+    let current = message_repository::get_for_update(message_id, &mut *tx).await?;
+    if current.deleted_at.is_some() {
+        return Err(RequestError::NotFound(
+            "Message has already been deleted".into(),
+        ));
+    }
+
+    let is_allowed =
+        current.user_id == user_id || can_moderate(user_id, current.room_id, &mut tx).await?;
+    if !is_allowed {
+        return Err(RequestError::Forbidden(
+            "Only the author or a moderator can delete a message".into(),
+        ));
+    }
+
+    let deleted = message_repository::soft_delete(message_id, user_id, &mut *tx).await?;
+    let parent = match deleted.parent_id {
+        Some(parent_id) => Some(message_repository::forget_reply(parent_id, &mut *tx).await?),
+        None => None,
+    };
+    let pin = pin_repository::delete(message_id, &mut *tx).await?;
+    tx.commit().await?;
+
+    let unpinned = pin.map(|pin| PinnedMessageResponse::new(deleted.clone(), pin));
+
+    let deleted = MessageResponse::from(deleted);
+    hub.broadcast(
+        deleted.room_id,
+        &ServerEvent::MessageDeleted(deleted.clone()),
+    );
+    if let Some(parent) = parent {
+        hub.broadcast(deleted.room_id, &ServerEvent::ThreadUpdated(parent.into()));
+    }
+    if let Some(unpinned) = unpinned {
+        hub.broadcast(deleted.room_id, &ServerEvent::MessageUnpinned(unpinned));
+    }
+
+    Ok(deleted)
+}
+
+pub async fn pin_message(
+    user_id: Uuid,
+    message_id: Uuid,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<PinnedMessageResponse> {
+    let mut tx = pool.begin().await?;
+
+    let message = message_repository::get_for_update(message_id, &mut *tx).await?;
+    ensure_can_pin(user_id, &message, &mut tx).await?;
+    if message.deleted_at.is_some() {
+        return Err(RequestError::Conflict(
+            "Deleted messages cannot be pinned".into(),
+        ));
+    }
+
+    let pin = pin_repository::create(message_id, message.room_id, user_id, &mut *tx)
+        .await?
+        .ok_or(RequestError::Conflict("Message is already pinned".into()))?;
+    tx.commit().await?;
+
+    let pinned = PinnedMessageResponse::new(message, pin);
+    hub.broadcast(
+        pinned.message.room_id,
+        &ServerEvent::MessagePinned(pinned.clone()),
+    );
+
+    Ok(pinned)
+}
+
+pub async fn unpin_message(
+    user_id: Uuid,
+    message_id: Uuid,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<PinnedMessageResponse> {
+    let mut tx = pool.begin().await?;
+
+    let message = message_repository::get_for_update(message_id, &mut *tx).await?;
+    ensure_can_pin(user_id, &message, &mut tx).await?;
+
+    let pin = pin_repository::delete(message_id, &mut *tx)
+        .await?
+        .ok_or(RequestError::NotFound("Message is not pinned".into()))?;
+    tx.commit().await?;
+
+    let unpinned = PinnedMessageResponse::new(message, pin);
+    hub.broadcast(
+        unpinned.message.room_id,
+        &ServerEvent::MessageUnpinned(unpinned.clone()),
+    );
+
+    Ok(unpinned)
+}
+
+pub async fn list_pins(
+    user_id: Uuid,
+    room_id: Uuid,
+    pool: &PgPool,
+) -> RequestResult<Vec<PinnedMessageResponse>> {
+    room_service::ensure_member(room_id, user_id, pool).await?;
+
+    let pins = pin_repository::list(room_id, pool).await?;
+    let message_ids = pins.iter().map(|pin| pin.message_id).collect::<Vec<_>>();
+    let mut messages = message_repository::list_by_ids(&message_ids, pool)
+        .await?
+        .into_iter()
+        .map(|message| (message.id, message))
+        .collect::<HashMap<_, _>>();
+
+    Ok(pins
+        .into_iter()
+        .filter_map(|pin| {
+            let message = messages.remove(&pin.message_id)?;
+            Some(PinnedMessageResponse::new(message, pin))
+        })
+        .collect())
+}
+
+async fn ensure_can_pin(
+    user_id: Uuid,
+    message: &MessageEntity,
+    conn: &mut PgConnection,
+) -> RequestResult<()> {
+    room_service::ensure_member(message.room_id, user_id, &mut *conn).await?;
+
+    if !can_moderate(user_id, message.room_id, conn).await? {
+        return Err(RequestError::Forbidden(
+            "Only room admins and moderators can pin messages".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+// global moderators, and owners and admins of the room
+async fn can_moderate(
+    user_id: Uuid,
+    room_id: Uuid,
+    conn: &mut PgConnection,
+) -> RequestResult<bool> {
+    if role_repository::has_any(user_id, &MODERATOR_ROLES, &mut *conn).await? {
+        return Ok(true);
+    }
+
+    let member = room_repository::get_member(room_id, user_id, &mut *conn).await?;
+
+    Ok(member.is_some_and(|member| member.role().can_moderate()))
+}
+
+pub async fn purge_deleted(retention: Duration, pool: &PgPool) -> RequestResult<i64> {
+    message_repository::purge_deleted(Utc::now() - retention, pool).await
+}
+
+pub async fn list_revisions(
+    user_id: Uuid,
+    message_id: Uuid,
+    pool: &PgPool,
+) -> RequestResult<Vec<MessageRevisionResponse>> {
+    if !role_repository::has_any(user_id, &MODERATOR_ROLES, pool).await? {
+        return Err(RequestError::Forbidden(
+            "Only moderators can view edit history".into(),
+        ));
+    }
+
+    message_repository::get(message_id, pool).await?;
+    message_repository::list_revisions(message_id, pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::app::{
+        models::{profiles::ValidCreateProfileRequest, rooms::domain::RoomRole},
+        repositories::{profile_repository, room_repository, user_repository},
+    };
+
+    fn content(text: &str) -> ValidEditMessageRequest {
+        ValidEditMessageRequest {
+            content: text.to_string().try_into().unwrap(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_edit_message(pool: PgPool) {
+        let hub = ChatHub::default();
+        let link_jobs = Notify::new();
+        let author = user_repository::create("author@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let other = user_repository::create("other@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, author, &pool)
+            .await
+            .unwrap();
+        room_repository::add_member(room.id, author, RoomRole::Owner, &pool)
+            .await
+            .unwrap();
+        room_repository::add_member(room.id, other, RoomRole::Member, &pool)
+            .await
+            .unwrap();
+
+        let message = ValidCreateMessageRequest {
+            content: "first".to_string().try_into().unwrap(),
+            parent_id: None,
+            attachment_ids: Vec::new(),
+        };
+        let message = send_message(author, room.id, message, &pool, &hub, &link_jobs)
+            .await
+            .unwrap();
+        assert_eq!(message.content_html, "<p>first</p>");
+
+        let (_, mut rx) = hub.register(other, vec![room.id]);
+
+        let edited = edit_message(
+            author,
+            message.id,
+            content("**second** <b>"),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .unwrap();
+        assert_eq!(edited.content, "**second** <b>");
+        assert_eq!(
+            edited.content_html,
+            "<p><strong>second</strong> &lt;b&gt;</p>"
+        );
+        assert!(edited.edited_at.is_some());
+        assert!(rx.try_recv().is_ok());
+
+        let error = edit_message(
+            other,
+            message.id,
+            content("hijacked"),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .err()
+        .unwrap();
+        let exp = expect!["403 Forbidden. Context: Only the author can edit a message"];
+        exp.assert_eq(&error.to_string());
+
+        let error = list_revisions(other, message.id, &pool)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["403 Forbidden. Context: Only moderators can view edit history"];
+        exp.assert_eq(&error.to_string());
+
+        role_repository::assign(other, "moderator", &pool)
+            .await
+            .unwrap();
+        let revisions = list_revisions(other, message.id, &pool).await.unwrap();
+        let contents = revisions
+            .iter()
+            .map(|r| r.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(contents, vec!["first"]);
+    }
+
+    #[sqlx::test]
+    async fn test_thread_replies(pool: PgPool) {
+        let hub = ChatHub::default();
+        let link_jobs = Notify::new();
+        let author = user_repository::create("author@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, author, &pool)
+            .await
+            .unwrap();
+        room_repository::add_member(room.id, author, RoomRole::Owner, &pool)
+            .await
+            .unwrap();
+
+        let message = |text: &str, parent_id| ValidCreateMessageRequest {
+            content: text.to_string().try_into().unwrap(),
+            parent_id,
+            attachment_ids: Vec::new(),
+        };
+        let parent = send_message(
+            author,
+            room.id,
+            message("parent", None),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .unwrap();
+
+        let mut replies = Vec::new();
+        for i in 0..3 {
+            let reply = message(&format!("reply {i}"), Some(parent.id));
+            replies.push(
+                send_message(author, room.id, reply, &pool, &hub, &link_jobs)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let nested = message("nested", Some(replies[0].id));
+        let error = send_message(author, room.id, nested, &pool, &hub, &link_jobs)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["400 Bad Request. Context: Replies cannot have replies"];
+        exp.assert_eq(&error.to_string());
+
+        delete_message(author, replies[1].id, &pool, &hub)
+            .await
+            .unwrap();
+
+        let query = |before| ValidMessageHistoryQuery {
+            before,
+            limit: None.try_into().unwrap(),
+        };
+        let thread = get_thread(author, parent.id, query(None), &pool)
+            .await
+            .unwrap();
+        assert_eq!(thread.parent.reply_count, 2);
+        assert_eq!(thread.parent.last_reply_at, Some(replies[2].created_at));
+        assert_eq!(thread.replies.len(), 3);
+
+        let page = get_thread(author, parent.id, query(Some(replies[1].id)), &pool)
+            .await
+            .unwrap();
+        let ids = page.replies.iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![replies[0].id]);
+
+        let history = list_messages(author, room.id, query(None), &pool)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+
+        // deleting the newest reply moves last_reply_at back, deleting all of them clears it
+        delete_message(author, replies[2].id, &pool, &hub)
+            .await
+            .unwrap();
+        let thread = get_thread(author, parent.id, query(None), &pool)
+            .await
+            .unwrap();
+        assert_eq!(thread.parent.reply_count, 1);
+        assert_eq!(thread.parent.last_reply_at, Some(replies[0].created_at));
+
+        delete_message(author, replies[0].id, &pool, &hub)
+            .await
+            .unwrap();
+        let thread = get_thread(author, parent.id, query(None), &pool)
+            .await
+            .unwrap();
+        assert_eq!(thread.parent.reply_count, 0);
+        assert_eq!(thread.parent.last_reply_at, None);
+    }
+
+    #[sqlx::test]
+    async fn test_mentions(pool: PgPool) {
+        let hub = ChatHub::default();
+        let link_jobs = Notify::new();
+
+        let mut users = Vec::new();
+        for name in ["alice", "bob", "carol"] {
+            let user_id = user_repository::create(&format!("{name}@gmail.com"), "pass", &pool)
+                .await
+                .unwrap();
+            let profile = ValidCreateProfileRequest {
+                username: name.to_string().try_into().unwrap(),
+                age: 20.try_into().unwrap(),
+                about_me: String::new().try_into().unwrap(),
+            };
+            profile_repository::create(user_id, profile, &pool)
+                .await
+                .unwrap();
+            users.push(user_id);
+        }
+        let [alice, bob, carol] = users[..] else {
+            unreachable!()
+        };
+        let room = room_repository::create("general", false, alice, &pool)
+            .await
+            .unwrap();
+        // carol is not in the room, so mentioning her does nothing
+        for user_id in [alice, bob] {
+            room_repository::add_member(room.id, user_id, RoomRole::Member, &pool)
+                .await
+                .unwrap();
+        }
+
+        let (_, mut bob_rx) = hub.register(bob, vec![]);
+        let (_, mut carol_rx) = hub.register(carol, vec![]);
+
+        let message = ValidCreateMessageRequest {
+            content: "@alice @bob @carol @nobody look"
+                .to_string()
+                .try_into()
+                .unwrap(),
+            parent_id: None,
+            attachment_ids: Vec::new(),
+        };
+        let message = send_message(alice, room.id, message, &pool, &hub, &link_jobs)
+            .await
+            .unwrap();
+        assert!(bob_rx.try_recv().is_ok());
+        assert!(carol_rx.try_recv().is_err());
+
+        let query = || ValidMessageHistoryQuery {
+            before: None,
+            limit: None.try_into().unwrap(),
+        };
+        let mentions = list_mentions(bob, query(), &pool).await.unwrap();
+        assert_eq!(mentions.len(), 1);
+        assert!(
+            list_mentions(alice, query(), &pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // editing the mention away removes it, and re-adding it notifies again
+        edit_message(
+            alice,
+            message.id,
+            content("nevermind"),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .unwrap();
+        assert!(list_mentions(bob, query(), &pool).await.unwrap().is_empty());
+
+        edit_message(
+            alice,
+            message.id,
+            content("@bob look"),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .unwrap();
+        assert!(bob_rx.try_recv().is_ok());
+        assert_eq!(list_mentions(bob, query(), &pool).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_pins(pool: PgPool) {
+        let hub = ChatHub::default();
+        let link_jobs = Notify::new();
+        let owner = user_repository::create("owner@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let member = user_repository::create("member@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, owner, &pool)
+            .await
+            .unwrap();
+        for (user_id, role) in [(owner, RoomRole::Owner), (member, RoomRole::Member)] {
+            room_repository::add_member(room.id, user_id, role, &pool)
+                .await
+                .unwrap();
+        }
+        let message = message_repository::create(room.id, member, "pin me", "", None, &pool)
+            .await
+            .unwrap();
+
+        let error = pin_message(member, message.id, &pool, &hub)
+            .await
+            .err()
+            .unwrap();
+        let exp =
+            expect!["403 Forbidden. Context: Only room admins and moderators can pin messages"];
+        exp.assert_eq(&error.to_string());
+
+        pin_message(owner, message.id, &pool, &hub).await.unwrap();
+        let error = pin_message(owner, message.id, &pool, &hub)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["409 Conflict. Context: Message is already pinned"];
+        exp.assert_eq(&error.to_string());
+
+        // pins follow edits of the message
+        edit_message(
+            member,
+            message.id,
+            content("edited"),
+            &pool,
+            &hub,
+            &link_jobs,
+        )
+        .await
+        .unwrap();
+        let pins = list_pins(member, room.id, &pool).await.unwrap();
+        let contents = pins
+            .iter()
+            .map(|pin| pin.message.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(contents, vec!["edited"]);
+
+        let (_, mut rx) = hub.register(member, vec![room.id]);
+        delete_message(member, message.id, &pool, &hub)
+            .await
+            .unwrap();
+        assert!(list_pins(member, room.id, &pool).await.unwrap().is_empty());
+        let events = std::iter::from_fn(|| rx.try_recv().ok()).count();
+        assert_eq!(events, 2);
+    }
+}
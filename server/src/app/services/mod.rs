@@ -1,2 +1,11 @@
+pub mod attachment_service;
+pub mod health_service;
+pub mod link_preview_service;
+pub mod message_service;
+pub mod presence_service;
 pub mod profile_service;
+pub mod reaction_service;
+pub mod room_service;
+pub mod search_service;
 pub mod user_service;
+pub mod ws_ticket_service;
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::app::{
+    models::{
+        events::ServerEvent,
+        presence::{PresenceResponse, ValidSetPresenceRequest, domain::PresenceStatus},
+    },
+    realtime::hub::ChatHub,
+    repositories::{room_repository, user_repository},
+    request_error::RequestResult,
+    services::room_service,
+};
+
+pub async fn set_presence(
+    user_id: Uuid,
+    presence: ValidSetPresenceRequest,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<PresenceResponse> {
+    let stored = user_repository::set_presence_override(
+        user_id,
+        presence.status.as_ref().map(PresenceStatus::as_str),
+        pool,
+    )
+    .await?;
+
+    let before = hub.presence(user_id);
+    hub.set_override(user_id, presence.status);
+    notify_change(user_id, before, &hub.peers(user_id), None, hub);
+
+    Ok(PresenceResponse {
+        user_id,
+        status: hub.presence(user_id),
+        last_seen_at: stored.last_seen_at,
+    })
+}
+
+pub async fn list_room_presence(
+    user_id: Uuid,
+    room_id: Uuid,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<Vec<PresenceResponse>> {
+    room_service::ensure_member(room_id, user_id, pool).await?;
+
+    let members = room_repository::list_member_presence(room_id, pool).await?;
+
+    Ok(members
+        .into_iter()
+        .map(|member| PresenceResponse {
+            user_id: member.user_id,
+            status: hub.presence(member.user_id),
+            last_seen_at: member.last_seen_at,
+        })
+        .collect())
+}
+
+// called once the session is registered with the hub
+pub fn connected(
+    user_id: Uuid,
+    before: PresenceStatus,
+    presence_override: Option<PresenceStatus>,
+    hub: &ChatHub,
+) {
+    hub.set_override(user_id, presence_override);
+    notify_change(user_id, before, &hub.peers(user_id), None, hub);
+}
+
+pub fn set_idle(user_id: Uuid, session_id: Uuid, idle: bool, hub: &ChatHub) {
+    let before = hub.presence(user_id);
+    hub.set_idle(session_id, idle);
+    notify_change(user_id, before, &hub.peers(user_id), None, hub);
+}
+
+// unregisters the session; the last one going away stamps `last_seen_at`
+pub async fn disconnected(user_id: Uuid, session_id: Uuid, pool: &PgPool, hub: &ChatHub) {
+    let before = hub.presence(user_id);
+    // the rooms of the user are forgotten by the hub together with its last session
+    let peers = hub.peers(user_id);
+    hub.unregister(session_id);
+
+    if hub.presence(user_id) != PresenceStatus::Offline {
+        notify_change(user_id, before, &peers, None, hub);
+        return;
+    }
+
+    let last_seen_at = match user_repository::touch_last_seen(user_id, pool).await {
+        Ok(last_seen_at) => Some(last_seen_at),
+        Err(e) => {
+            tracing::error!("Failed to update last seen: {}", e);
+            None
+        }
+    };
+    notify_change(user_id, before, &peers, last_seen_at, hub);
+}
+
+fn notify_change(
+    user_id: Uuid,
+    before: PresenceStatus,
+    peers: &HashSet<Uuid>,
+    last_seen_at: Option<DateTime<Utc>>,
+    hub: &ChatHub,
+) {
+    let status = hub.presence(user_id);
+    if status == before {
+        return;
+    }
+
+    let event = ServerEvent::PresenceChanged(PresenceResponse {
+        user_id,
+        status,
+        last_seen_at,
+    });
+    for peer in peers {
+        hub.send_to_user(*peer, &event);
+    }
+}
@@ -1 +1,283 @@
+use bytes::Bytes;
+use sqlx::PgPool;
+use uuid::Uuid;
 
+use crate::{
+    app::{
+        media::avatars::{self, AVATAR_SIZES},
+        models::{
+            attachments::UploadedFile,
+            profiles::{ProfileResponse, ValidAvatarQuery},
+        },
+        repositories::profile_repository,
+        request_error::{RequestError, RequestResult},
+    },
+    core::{app_config::UploadLimits, storage::StorageBackend},
+};
+
+pub async fn get_profile(user_id: Uuid, pool: &PgPool) -> RequestResult<ProfileResponse> {
+    profile_repository::get_by_user_id(user_id, pool)
+        .await
+        .map(ProfileResponse::from)
+}
+
+// replaces the current avatar, if any
+pub async fn set_avatar(
+    user_id: Uuid,
+    file: UploadedFile,
+    limits: &UploadLimits,
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+) -> RequestResult<ProfileResponse> {
+    if file.data.len() > limits.max_size_bytes {
+        return Err(RequestError::PayloadTooLarge(format!(
+            "Files may not be larger than {} bytes",
+            limits.max_size_bytes
+        )));
+    }
+
+    // the declared content type does not matter, the picture has to decode
+    let data = file.data;
+    let cropped = tokio::task::spawn_blocking(move || avatars::crop(&data))
+        .await
+        .map_err(|e| RequestError::InternalServerError(e.to_string()))?
+        .map_err(|e| {
+            tracing::warn!("cannot decode avatar of user {}: {}", user_id, e);
+            RequestError::UnsupportedMediaType(
+                "Avatars must be PNG, JPEG, GIF or WebP images".into(),
+            )
+        })?;
+
+    // a fresh key per upload, so clients never get an old picture for the new URL
+    let key = format!("avatars/{user_id}/{}", Uuid::new_v4());
+    let content_type = cropped[0].content_type;
+
+    let stored = store(&key, cropped, storage).await;
+    let updated = match stored {
+        Ok(()) => {
+            profile_repository::set_avatar(user_id, Some(&key), Some(content_type), pool).await
+        }
+        Err(e) => Err(e),
+    };
+    if updated.is_err() {
+        discard(&key, storage).await;
+    }
+    let (updated, previous) = updated?;
+
+    if let Some(previous) = previous {
+        discard(&previous, storage).await;
+    }
+
+    Ok(updated.into())
+}
+
+// back to the generated identicon
+pub async fn delete_avatar(
+    user_id: Uuid,
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+) -> RequestResult<ProfileResponse> {
+    let (updated, previous) = profile_repository::set_avatar(user_id, None, None, pool).await?;
+
+    if let Some(previous) = previous {
+        discard(&previous, storage).await;
+    }
+
+    Ok(updated.into())
+}
+
+// returns the content type and the picture, users without an avatar get their identicon
+pub async fn get_avatar(
+    user_id: Uuid,
+    query: ValidAvatarQuery,
+    pool: &PgPool,
+    storage: &dyn StorageBackend,
+) -> RequestResult<(String, Bytes)> {
+    let size = query.size;
+
+    match profile_repository::find_avatar(user_id, pool).await? {
+        Some((key, content_type)) => {
+            let data = storage.get(&format!("{key}.{}", size.name())).await?;
+            Ok((content_type, data))
+        }
+        None => {
+            let data =
+                tokio::task::spawn_blocking(move || avatars::identicon(user_id, size.edge()))
+                    .await
+                    .map_err(|e| RequestError::InternalServerError(e.to_string()))?
+                    .map_err(|e| RequestError::InternalServerError(e.to_string()))?;
+            Ok(("image/png".into(), data))
+        }
+    }
+}
+
+async fn store(
+    key: &str,
+    cropped: Vec<avatars::Avatar>,
+    storage: &dyn StorageBackend,
+) -> RequestResult<()> {
+    for avatar in cropped {
+        storage
+            .put(
+                &format!("{key}.{}", avatar.size),
+                avatar.content_type,
+                avatar.data,
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+// best effort, a replaced avatar should not leave objects behind
+async fn discard(key: &str, storage: &dyn StorageBackend) {
+    for (size, _) in AVATAR_SIZES {
+        let key = format!("{key}.{size}");
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!("failed to delete avatar object {}: {}", key, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::mime;
+    use expect_test::expect;
+
+    use super::*;
+    use crate::{
+        app::{
+            models::profiles::{AvatarQuery, ValidCreateProfileRequest},
+            repositories::user_repository,
+        },
+        core::storage::LocalStorage,
+    };
+
+    fn png(width: u32, height: u32) -> UploadedFile {
+        let mut data = Vec::new();
+        image::RgbImage::from_pixel(width, height, image::Rgb([0, 128, 255]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        UploadedFile {
+            filename: "me.png".into(),
+            content_type: Some(mime::IMAGE_PNG),
+            data: data.into(),
+        }
+    }
+
+    fn size(size: &str) -> ValidAvatarQuery {
+        AvatarQuery {
+            size: Some(size.into()),
+        }
+        .try_into()
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_avatars(pool: PgPool) {
+        let root = std::env::temp_dir().join(format!("web_chat_{}", Uuid::new_v4()));
+        let storage = LocalStorage::new(&root);
+        let limits = UploadLimits::default();
+
+        let user_id = user_repository::create("avatar@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let profile = ValidCreateProfileRequest {
+            username: "avatar".to_string().try_into().unwrap(),
+            age: 30.try_into().unwrap(),
+            about_me: String::new().try_into().unwrap(),
+        };
+        profile_repository::create(user_id, profile, &pool)
+            .await
+            .unwrap();
+
+        let profile = get_profile(user_id, &pool).await.unwrap();
+        assert_eq!(profile.avatar_url, format!("/users/{user_id}/avatar"));
+        let (content_type, identicon) = get_avatar(user_id, size("small"), &pool, &storage)
+            .await
+            .unwrap();
+        assert_eq!(content_type, "image/png");
+        assert_eq!(image::load_from_memory(&identicon).unwrap().width(), 64);
+
+        let error = set_avatar(
+            user_id,
+            UploadedFile {
+                filename: "me.png".into(),
+                content_type: Some(mime::IMAGE_PNG),
+                data: Bytes::from_static(b"not a png"),
+            },
+            &limits,
+            &pool,
+            &storage,
+        )
+        .await
+        .err()
+        .unwrap();
+        let exp = expect![
+            "415 Unsupported Media Type. Context: Avatars must be PNG, JPEG, GIF or WebP images"
+        ];
+        exp.assert_eq(&error.to_string());
+
+        let profile = set_avatar(user_id, png(300, 200), &limits, &pool, &storage)
+            .await
+            .unwrap();
+        assert!(
+            profile
+                .avatar_url
+                .starts_with(&format!("/users/{user_id}/avatar?v="))
+        );
+        let (content_type, data) = get_avatar(user_id, size("large"), &pool, &storage)
+            .await
+            .unwrap();
+        assert_eq!(content_type, "image/jpeg");
+        let image = image::load_from_memory(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (256, 256));
+
+        // replacing removes the previous pictures
+        let (first, _) = profile_repository::find_avatar(user_id, &pool)
+            .await
+            .unwrap()
+            .unwrap();
+        set_avatar(user_id, png(32, 32), &limits, &pool, &storage)
+            .await
+            .unwrap();
+        assert!(storage.get(&format!("{first}.small")).await.is_err());
+
+        // concurrent uploads each remove what they replaced, only the last one is kept
+        let (first, second) = tokio::join!(
+            set_avatar(user_id, png(40, 40), &limits, &pool, &storage),
+            set_avatar(user_id, png(50, 50), &limits, &pool, &storage),
+        );
+        first.unwrap();
+        second.unwrap();
+        let mut objects = tokio::fs::read_dir(root.join("avatars").join(user_id.to_string()))
+            .await
+            .unwrap();
+        let mut count = 0;
+        while objects.next_entry().await.unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, AVATAR_SIZES.len());
+
+        let profile = delete_avatar(user_id, &pool, &storage).await.unwrap();
+        assert_eq!(profile.avatar_url, format!("/users/{user_id}/avatar"));
+        let (_, data) = get_avatar(user_id, size("small"), &pool, &storage)
+            .await
+            .unwrap();
+        assert_eq!(data, identicon);
+
+        let error = AvatarQuery {
+            size: Some("huge".into()),
+        };
+        let error = ValidAvatarQuery::try_from(error).err().unwrap();
+        let exp =
+            expect!["400 Bad Request. Context: Avatar size must be one of small, medium, large"];
+        exp.assert_eq(&error.to_string());
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::app::{
+    models::{
+        events::ServerEvent,
+        messages::MessageResponse,
+        reactions::{ReactionResponse, ReactionSummary, domain::ReactionEmoji},
+    },
+    realtime::hub::ChatHub,
+    repositories::{message_repository, reaction_repository},
+    request_error::{RequestError, RequestResult},
+    services::room_service,
+};
+
+pub async fn add_reaction(
+    user_id: Uuid,
+    message_id: Uuid,
+    emoji: ReactionEmoji,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<ReactionResponse> {
+    let message = message_repository::get(message_id, pool).await?;
+    room_service::ensure_member(message.room_id, user_id, pool).await?;
+
+    if message.deleted_at.is_some() {
+        return Err(RequestError::Conflict(
+            "Cannot react to a deleted message".into(),
+        ));
+    }
+    if emoji.is_shortcode()
+        && !reaction_repository::custom_emoji_exists(emoji.as_ref(), pool).await?
+    {
+        return Err(RequestError::BadRequest("Unknown custom emoji".into()));
+    }
+
+    let reaction = reaction_repository::add(message_id, user_id, emoji.as_ref(), pool)
+        .await?
+        .ok_or(RequestError::Conflict(
+            "You have already reacted with this emoji".into(),
+        ))?;
+
+    hub.broadcast(
+        reaction.room_id,
+        &ServerEvent::ReactionAdded(reaction.clone()),
+    );
+
+    Ok(reaction)
+}
+
+pub async fn remove_reaction(
+    user_id: Uuid,
+    message_id: Uuid,
+    emoji: ReactionEmoji,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<ReactionResponse> {
+    let message = message_repository::get(message_id, pool).await?;
+    room_service::ensure_member(message.room_id, user_id, pool).await?;
+
+    let reaction = reaction_repository::remove(message_id, user_id, emoji.as_ref(), pool)
+        .await?
+        .ok_or(RequestError::NotFound("Reaction not found".into()))?;
+
+    hub.broadcast(
+        reaction.room_id,
+        &ServerEvent::ReactionRemoved(reaction.clone()),
+    );
+
+    Ok(reaction)
+}
+
+// fills in the reaction counts of a page of messages with a single query
+pub async fn attach_reactions(
+    user_id: Uuid,
+    messages: &mut [MessageResponse],
+    pool: &PgPool,
+) -> RequestResult<()> {
+    let message_ids = messages.iter().map(|m| m.id).collect::<Vec<_>>();
+    let counts = reaction_repository::count_for_messages(&message_ids, user_id, pool).await?;
+
+    let mut reactions = HashMap::<Uuid, Vec<ReactionSummary>>::new();
+    for count in counts {
+        reactions
+            .entry(count.message_id)
+            .or_default()
+            .push(count.into());
+    }
+
+    for message in messages {
+        message.reactions = reactions.remove(&message.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::app::{
+        models::{messages::ValidMessageHistoryQuery, rooms::domain::RoomRole},
+        repositories::{room_repository, user_repository},
+        services::message_service,
+    };
+
+    fn emoji(value: &str) -> ReactionEmoji {
+        value.to_string().try_into().unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_reactions(pool: PgPool) {
+        let hub = ChatHub::default();
+        let author = user_repository::create("author@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let other = user_repository::create("other@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, author, &pool)
+            .await
+            .unwrap();
+        for (user_id, role) in [(author, RoomRole::Owner), (other, RoomRole::Member)] {
+            room_repository::add_member(room.id, user_id, role, &pool)
+                .await
+                .unwrap();
+        }
+        let message = message_repository::create(room.id, author, "hello", "", None, &pool)
+            .await
+            .unwrap();
+
+        let (_, mut rx) = hub.register(author, vec![room.id]);
+
+        for user_id in [author, other] {
+            add_reaction(user_id, message.id, emoji("👍🏽"), &pool, &hub)
+                .await
+                .unwrap();
+        }
+        assert!(rx.try_recv().is_ok());
+
+        let error = add_reaction(other, message.id, emoji("👍🏽"), &pool, &hub)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["409 Conflict. Context: You have already reacted with this emoji"];
+        exp.assert_eq(&error.to_string());
+
+        let error = add_reaction(other, message.id, emoji(":party_parrot:"), &pool, &hub)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["400 Bad Request. Context: Unknown custom emoji"];
+        exp.assert_eq(&error.to_string());
+
+        sqlx::query!("INSERT INTO custom_emojis (shortcode) VALUES (':party_parrot:')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        add_reaction(other, message.id, emoji(":party_parrot:"), &pool, &hub)
+            .await
+            .unwrap();
+
+        let query = ValidMessageHistoryQuery {
+            before: None,
+            limit: None.try_into().unwrap(),
+        };
+        let history = message_service::list_messages(author, room.id, query, &pool)
+            .await
+            .unwrap();
+        let reactions = history[0]
+            .reactions
+            .iter()
+            .map(|r| (r.emoji.as_str(), r.count, r.reacted))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reactions,
+            vec![("👍🏽", 2, true), (":party_parrot:", 1, false)]
+        );
+
+        remove_reaction(other, message.id, emoji("👍🏽"), &pool, &hub)
+            .await
+            .unwrap();
+        let error = remove_reaction(other, message.id, emoji("👍🏽"), &pool, &hub)
+            .await
+            .err()
+            .unwrap();
+        let exp = expect!["404 Not Found. Context: Reaction not found"];
+        exp.assert_eq(&error.to_string());
+    }
+}
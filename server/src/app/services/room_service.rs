@@ -0,0 +1,241 @@
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::app::{
+    models::{
+        events::ServerEvent,
+        rooms::{
+            ReadReceiptResponse, RoomInviteResponse, RoomMemberEntity, RoomResponse,
+            UnreadCountResponse, ValidCreateRoomRequest, domain::RoomRole,
+        },
+    },
+    realtime::hub::ChatHub,
+    repositories::{message_repository, role_repository, room_repository},
+    request_error::{RequestError, RequestResult},
+};
+
+// may join any private room without an invite
+const ADMIN_ROLES: [&str; 1] = ["admin"];
+
+pub async fn create_room(
+    user_id: Uuid,
+    room: ValidCreateRoomRequest,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<RoomResponse> {
+    let mut tx = pool.begin().await?;
+    let room =
+        room_repository::create(room.name.as_ref(), room.is_private, user_id, &mut *tx).await?;
+    room_repository::add_member(room.id, user_id, RoomRole::Owner, &mut *tx).await?;
+    tx.commit().await?;
+
+    hub.join(user_id, room.id);
+
+    Ok(room)
+}
+
+pub async fn list_rooms(user_id: Uuid, pool: &PgPool) -> RequestResult<Vec<RoomResponse>> {
+    room_repository::list_for_user(user_id, pool).await
+}
+
+pub async fn join_room(
+    user_id: Uuid,
+    room_id: Uuid,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<RoomResponse> {
+    let mut tx = pool.begin().await?;
+    let room = room_repository::get(room_id, &mut *tx).await?;
+    if room.is_private && !can_join_private(room_id, user_id, &mut tx).await? {
+        return Err(RequestError::Forbidden(
+            "This room is private, joining it takes an invite".into(),
+        ));
+    }
+    room_repository::add_member(room_id, user_id, RoomRole::Member, &mut *tx).await?;
+    tx.commit().await?;
+
+    hub.join(user_id, room_id);
+
+    Ok(room)
+}
+
+// members, invited users (the invite is used up) and global admins
+async fn can_join_private(
+    room_id: Uuid,
+    user_id: Uuid,
+    conn: &mut PgConnection,
+) -> RequestResult<bool> {
+    if room_repository::get_member(room_id, user_id, &mut *conn)
+        .await?
+        .is_some()
+    {
+        return Ok(true);
+    }
+    if room_repository::take_invite(room_id, user_id, &mut *conn).await? {
+        return Ok(true);
+    }
+
+    role_repository::has_any(user_id, &ADMIN_ROLES, &mut *conn).await
+}
+
+pub async fn invite_user(
+    user_id: Uuid,
+    room_id: Uuid,
+    invitee_id: Uuid,
+    pool: &PgPool,
+) -> RequestResult<RoomInviteResponse> {
+    let member = ensure_member(room_id, user_id, pool).await?;
+    if !member.role().can_moderate() {
+        return Err(RequestError::Forbidden(
+            "Only owners and admins of the room can invite".into(),
+        ));
+    }
+
+    room_repository::create_invite(room_id, invitee_id, user_id, pool).await
+}
+
+pub async fn leave_room(
+    user_id: Uuid,
+    room_id: Uuid,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<Uuid> {
+    let room_id = room_repository::remove_member(room_id, user_id, pool).await?;
+
+    hub.leave(user_id, room_id);
+
+    Ok(room_id)
+}
+
+pub async fn list_unread(user_id: Uuid, pool: &PgPool) -> RequestResult<Vec<UnreadCountResponse>> {
+    room_repository::unread_counts(user_id, pool).await
+}
+
+pub async fn mark_read(
+    user_id: Uuid,
+    room_id: Uuid,
+    message_id: Uuid,
+    pool: &PgPool,
+    hub: &ChatHub,
+) -> RequestResult<ReadReceiptResponse> {
+    ensure_member(room_id, user_id, pool).await?;
+
+    let message = message_repository::get(message_id, pool).await?;
+    if message.room_id != room_id {
+        return Err(RequestError::BadRequest(
+            "Message belongs to another room".into(),
+        ));
+    }
+
+    // the caller's other devices and the rest of the room only hear about progress
+    match room_repository::mark_read(room_id, user_id, message_id, pool).await? {
+        Some(receipt) => {
+            hub.broadcast(room_id, &ServerEvent::MessagesRead(receipt.clone()));
+            Ok(receipt)
+        }
+        None => room_repository::get_read_receipt(room_id, user_id, pool).await,
+    }
+}
+
+pub async fn ensure_member<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    exec: E,
+) -> RequestResult<RoomMemberEntity>
+where
+    E: PgExecutor<'c>,
+{
+    room_repository::get_member(room_id, user_id, exec)
+        .await?
+        .ok_or(RequestError::Forbidden(
+            "You are not a member of this room".into(),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::repositories::user_repository;
+
+    #[sqlx::test]
+    async fn test_unread_counts(pool: PgPool) {
+        let hub = ChatHub::default();
+        let alice = user_repository::create("alice@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let bob = user_repository::create("bob@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = ValidCreateRoomRequest {
+            name: "general".to_string().try_into().unwrap(),
+            is_private: false,
+        };
+        let room = create_room(alice, room, &pool, &hub).await.unwrap();
+        join_room(bob, room.id, &pool, &hub).await.unwrap();
+
+        let mut messages = Vec::new();
+        for i in 0..3 {
+            let message =
+                message_repository::create(room.id, alice, &format!("{i}"), "", None, &pool)
+                    .await
+                    .unwrap();
+            messages.push(message.id);
+        }
+
+        let unread = async |user_id| list_unread(user_id, &pool).await.unwrap()[0].unread_count;
+        assert_eq!(unread(bob).await, 3);
+        // own messages are never unread
+        assert_eq!(unread(alice).await, 0);
+
+        let receipt = mark_read(bob, room.id, messages[1], &pool, &hub)
+            .await
+            .unwrap();
+        assert_eq!(receipt.last_read_message_id, Some(messages[1]));
+        assert_eq!(unread(bob).await, 1);
+
+        // an older message does not move the marker back
+        let receipt = mark_read(bob, room.id, messages[0], &pool, &hub)
+            .await
+            .unwrap();
+        assert_eq!(receipt.last_read_message_id, Some(messages[1]));
+        assert_eq!(unread(bob).await, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_private_room(pool: PgPool) {
+        let hub = ChatHub::default();
+        let mut users = Vec::new();
+        for email in ["alice@gmail.com", "bob@gmail.com", "carol@gmail.com"] {
+            users.push(user_repository::create(email, "pass", &pool).await.unwrap());
+        }
+        let [alice, bob, carol] = users[..] else {
+            unreachable!()
+        };
+        let room = ValidCreateRoomRequest {
+            name: "staff".to_string().try_into().unwrap(),
+            is_private: true,
+        };
+        let room = create_room(alice, room, &pool, &hub).await.unwrap();
+        assert!(room.is_private);
+
+        let try_join = join_room(bob, room.id, &pool, &hub).await;
+        assert!(matches!(try_join, Err(RequestError::Forbidden(_))));
+
+        // only owners and admins of the room invite
+        let invite = invite_user(alice, room.id, bob, &pool).await.unwrap();
+        assert_eq!(invite.invited_by, Some(alice));
+        join_room(bob, room.id, &pool, &hub).await.unwrap();
+        let try_invite = invite_user(bob, room.id, carol, &pool).await;
+        assert!(matches!(try_invite, Err(RequestError::Forbidden(_))));
+
+        // the invite is used up by the join
+        leave_room(bob, room.id, &pool, &hub).await.unwrap();
+        let try_join = join_room(bob, room.id, &pool, &hub).await;
+        assert!(matches!(try_join, Err(RequestError::Forbidden(_))));
+
+        role_repository::assign(carol, "admin", &pool)
+            .await
+            .unwrap();
+        join_room(carol, room.id, &pool, &hub).await.unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::app::{
+    models::search::{SearchHitResponse, ValidSearchMessagesQuery},
+    repositories::message_repository,
+    request_error::RequestResult,
+    services::room_service,
+};
+
+pub async fn search_messages(
+    user_id: Uuid,
+    query: ValidSearchMessagesQuery,
+    pool: &PgPool,
+) -> RequestResult<Vec<SearchHitResponse>> {
+    // other rooms are filtered out anyway, but asking for one explicitly is an error
+    if let Some(room_id) = query.room {
+        room_service::ensure_member(room_id, user_id, pool).await?;
+    }
+
+    let hits = message_repository::search(user_id, &query, pool).await?;
+
+    Ok(hits.into_iter().map(SearchHitResponse::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{
+        models::rooms::domain::RoomRole,
+        repositories::{room_repository, user_repository},
+    };
+
+    fn query(q: &str, before: Option<Uuid>) -> ValidSearchMessagesQuery {
+        ValidSearchMessagesQuery {
+            q: q.to_string().try_into().unwrap(),
+            room: None,
+            from: None,
+            before,
+            limit: Some(2).try_into().unwrap(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_search_messages(pool: PgPool) {
+        let alice = user_repository::create("alice@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let bob = user_repository::create("bob@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+        let room = room_repository::create("general", false, alice, &pool)
+            .await
+            .unwrap();
+        let private = room_repository::create("private", true, bob, &pool)
+            .await
+            .unwrap();
+        room_repository::add_member(room.id, alice, RoomRole::Owner, &pool)
+            .await
+            .unwrap();
+        room_repository::add_member(private.id, bob, RoomRole::Owner, &pool)
+            .await
+            .unwrap();
+
+        let contents = [
+            "we decided to <b>deploy</b> on friday",
+            "lunch?",
+            "deployment moved to monday",
+            "the deploy went fine",
+        ];
+        for content in contents {
+            message_repository::create(room.id, alice, content, "", None, &pool)
+                .await
+                .unwrap();
+        }
+        message_repository::create(private.id, bob, "secret deploy", "", None, &pool)
+            .await
+            .unwrap();
+
+        let page = search_messages(alice, query("deploy", None), &pool)
+            .await
+            .unwrap();
+        let snippets = page
+            .iter()
+            .map(|hit| hit.snippet.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            snippets,
+            vec![
+                "the <mark>deploy</mark> went fine",
+                "<mark>deployment</mark> moved to monday"
+            ]
+        );
+
+        let page = search_messages(alice, query("deploy", Some(page[1].message.id)), &pool)
+            .await
+            .unwrap();
+        let snippets = page
+            .iter()
+            .map(|hit| hit.snippet.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            snippets,
+            vec!["we decided to &lt;b&gt;<mark>deploy</mark>&lt;/b&gt; on friday"]
+        );
+
+        let mut private_query = query("secret", None);
+        private_query.room = Some(private.id);
+        assert!(search_messages(alice, private_query, &pool).await.is_err());
+    }
+}
@@ -2,6 +2,7 @@ use argon2::{
     Argon2, PasswordHash, PasswordVerifier,
     password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
 };
+use chrono::Duration;
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -9,12 +10,15 @@ use crate::app::{
     extensions::jwt_coding,
     middlewares::jwt::Claims,
     models::users::{
-        UserResponse, ValidCreateUserRequest, ValidLoginUserRequest, ValidPatchUserRequest,
+        UserResponse, UserSummary, ValidCreateUserRequest, ValidLoginUserRequest,
+        ValidPatchUserRequest,
     },
-    repositories::user_repository,
+    repositories::{role_repository, user_repository},
     request_error::{RequestError, RequestResult},
 };
 
+const ADMIN_ROLE: &str = "admin";
+
 const DUMMY_HASH: &str =
     "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHR2YWx1ZQ$7H0vsXlY8UxxyW4TOHcDkME0mI0j6lf45i1HNrPzEzs";
 
@@ -27,6 +31,7 @@ pub async fn get_user(user_id: Uuid, pool: &PgPool) -> RequestResult<UserRespons
 pub async fn login_user(
     user: ValidLoginUserRequest,
     jwt_secret: &str,
+    jwt_ttl: Duration,
     pool: &PgPool,
 ) -> RequestResult<String> {
     let sql_result = user_repository::get_by_email(user.email.as_ref(), pool).await;
@@ -44,8 +49,16 @@ pub async fn login_user(
         })
         .unwrap_or(false);
 
+    if is_verified
+        && sql_result
+            .as_ref()
+            .is_ok_and(|entity| entity.disabled_at.is_some())
+    {
+        return Err(RequestError::Forbidden("User is disabled".into()));
+    }
+
     if is_verified {
-        let claims = Claims::from(sql_result.unwrap());
+        let claims = Claims::new(sql_result.unwrap(), jwt_ttl);
         let token = jwt_coding::encode_jwt(claims, jwt_secret)?;
         Ok(token)
     } else {
@@ -62,6 +75,39 @@ pub async fn create_user(user: ValidCreateUserRequest, pool: &PgPool) -> Request
     user_repository::create(email, password_hash.as_str(), pool).await
 }
 
+pub async fn create_admin(user: ValidCreateUserRequest, pool: &PgPool) -> RequestResult<Uuid> {
+    let email = user.email.as_ref();
+    let password_hash = generate_password_hash(user.password.as_ref().as_bytes())?;
+
+    let mut tx = pool.begin().await?;
+    let user_id = user_repository::create(email, password_hash.as_str(), &mut *tx).await?;
+    role_repository::assign(user_id, ADMIN_ROLE, &mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(user_id)
+}
+
+pub async fn list_disabled_users(pool: &PgPool) -> RequestResult<Vec<Uuid>> {
+    user_repository::list_disabled(pool).await
+}
+
+pub async fn list_users(limit: i64, offset: i64, pool: &PgPool) -> RequestResult<Vec<UserSummary>> {
+    user_repository::list(limit, offset, pool).await
+}
+
+// the server closes the live sessions of a user disabled from the CLI when it hears
+// about it on this channel
+pub const USER_DISABLED_CHANNEL: &str = "user_disabled";
+
+pub async fn disable_user(email: &str, pool: &PgPool) -> RequestResult<Uuid> {
+    let mut tx = pool.begin().await?;
+    let user_id = user_repository::disable(email, &mut *tx).await?;
+    user_repository::notify_disabled(USER_DISABLED_CHANNEL, user_id, &mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(user_id)
+}
+
 pub async fn patch_user(
     user_id: Uuid,
     mut user: ValidPatchUserRequest,
@@ -0,0 +1,82 @@
+use chrono::{Duration, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::app::{
+    models::ws_tickets::WsTicketResponse,
+    repositories::{user_repository, ws_ticket_repository},
+    request_error::{RequestError, RequestResult},
+};
+
+// long enough to open the socket right after asking for the ticket
+const TICKET_TTL_SECONDS: i64 = 30;
+const TICKET_BYTES: usize = 32;
+
+pub async fn issue_ticket(user_id: Uuid, pool: &PgPool) -> RequestResult<WsTicketResponse> {
+    let ticket = hex::encode(rand::rng().random::<[u8; TICKET_BYTES]>());
+    let expires_at = Utc::now() + Duration::seconds(TICKET_TTL_SECONDS);
+
+    // only the hash is stored, so the table cannot be replayed from a backup or a dump
+    ws_ticket_repository::create(&hash(&ticket), user_id, expires_at, pool).await?;
+
+    Ok(WsTicketResponse { ticket, expires_at })
+}
+
+pub async fn redeem_ticket(ticket: &str, pool: &PgPool) -> RequestResult<Uuid> {
+    let user_id = ws_ticket_repository::redeem(&hash(ticket), pool)
+        .await?
+        .ok_or(RequestError::Unauthorized(
+            "Invalid or expired ticket".into(),
+        ))?;
+
+    match user_repository::is_disabled(user_id, pool).await? {
+        Some(false) => Ok(user_id),
+        Some(true) => Err(RequestError::Forbidden("User is disabled".into())),
+        None => Err(RequestError::Unauthorized("User not found".into())),
+    }
+}
+
+fn hash(ticket: &str) -> String {
+    hex::encode(Sha256::digest(ticket.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_tickets(pool: PgPool) {
+        let user_id = user_repository::create("alice@gmail.com", "pass", &pool)
+            .await
+            .unwrap();
+
+        let response = issue_ticket(user_id, &pool).await.unwrap();
+        assert_eq!(response.ticket.len(), TICKET_BYTES * 2);
+        assert_eq!(
+            redeem_ticket(&response.ticket, &pool).await.unwrap(),
+            user_id
+        );
+
+        // single use
+        let result = redeem_ticket(&response.ticket, &pool).await;
+        assert!(matches!(result, Err(RequestError::Unauthorized(_))));
+        let result = redeem_ticket("unknown", &pool).await;
+        assert!(matches!(result, Err(RequestError::Unauthorized(_))));
+
+        let expired = "expired";
+        ws_ticket_repository::create(&hash(expired), user_id, Utc::now(), &pool)
+            .await
+            .unwrap();
+        let result = redeem_ticket(expired, &pool).await;
+        assert!(matches!(result, Err(RequestError::Unauthorized(_))));
+
+        let response = issue_ticket(user_id, &pool).await.unwrap();
+        user_repository::disable("alice@gmail.com", &pool)
+            .await
+            .unwrap();
+        let result = redeem_ticket(&response.ticket, &pool).await;
+        assert!(matches!(result, Err(RequestError::Forbidden(_))));
+    }
+}
@@ -0,0 +1,36 @@
+use clap::Subcommand;
+
+use crate::core::{
+    app_config::{AppConfig, ConfigOverrides},
+    app_error::{AppError, AppResult},
+};
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    #[command(about = "Load and validate the configuration, reporting every invalid field")]
+    Check,
+}
+
+pub fn run(command: ConfigCommand, overrides: ConfigOverrides) -> AppResult<()> {
+    match command {
+        ConfigCommand::Check => check(overrides),
+    }
+}
+
+fn check(overrides: ConfigOverrides) -> AppResult<()> {
+    match AppConfig::configure(overrides) {
+        Ok(_) => {
+            println!("configuration is valid");
+            Ok(())
+        }
+        Err(AppError::InvalidConfig(errors)) => {
+            println!("configuration is invalid:");
+            for error in &errors {
+                println!("  - {error}");
+            }
+
+            Err(AppError::InvalidConfig(errors))
+        }
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use clap::Subcommand;
+use sqlx::migrate::Migrate;
+
+use crate::core::{
+    app_config::{AppConfig, ConfigOverrides},
+    app_error::{AppError, AppResult},
+    database::{self, MIGRATOR},
+};
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    #[command(about = "Apply all pending migrations")]
+    Up,
+
+    #[command(about = "Revert migrations newer than the target version")]
+    Down {
+        #[arg(
+            long,
+            help = "Version to revert to (defaults to the one before the latest)"
+        )]
+        target: Option<i64>,
+    },
+
+    #[command(about = "List migrations and whether they are applied")]
+    Status,
+}
+
+pub async fn run(command: MigrateCommand, overrides: ConfigOverrides) -> AppResult<()> {
+    let config = AppConfig::configure(overrides)?;
+    let pool = database::connect(&config.database).await?;
+
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect::<HashSet<_>>();
+    drop(conn);
+
+    match command {
+        MigrateCommand::Up => {
+            MIGRATOR.run(&pool).await?;
+
+            let count = MIGRATOR
+                .iter()
+                .filter(|m| m.migration_type.is_up_migration() && !applied.contains(&m.version))
+                .count();
+            println!("applied {count} migration(s)");
+        }
+        MigrateCommand::Down { target } => {
+            let mut versions = applied.iter().copied().collect::<Vec<_>>();
+            versions.sort_unstable();
+
+            let target = target
+                .or_else(|| versions.iter().rev().nth(1).copied())
+                .unwrap_or(0);
+
+            let irreversible = versions
+                .iter()
+                .filter(|version| **version > target)
+                .filter(|version| {
+                    !MIGRATOR
+                        .iter()
+                        .any(|m| m.version == **version && m.migration_type.is_down_migration())
+                })
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            if !irreversible.is_empty() {
+                return Err(AppError::Other(format!(
+                    "migrations without a down script cannot be reverted: {}",
+                    irreversible.join(", ")
+                )));
+            }
+
+            MIGRATOR.undo(&pool, target).await?;
+            println!("reverted to version {target}");
+        }
+        MigrateCommand::Status => {
+            for migration in MIGRATOR
+                .iter()
+                .filter(|m| m.migration_type.is_up_migration())
+            {
+                let status = match applied.contains(&migration.version) {
+                    true => "applied",
+                    false => "pending",
+                };
+                println!(
+                    "{:<16} {:<8} {}",
+                    migration.version, status, migration.description
+                );
+            }
+        }
+    }
+
+    pool.close().await;
+    Ok(())
+}
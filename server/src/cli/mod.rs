@@ -0,0 +1,120 @@
+pub mod config_command;
+pub mod migrate_command;
+pub mod openapi_command;
+pub mod user_command;
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::core::{app_config::ConfigOverrides, app_error::AppResult, telemetry};
+
+#[derive(Parser)]
+#[command(
+    name = "server",
+    version,
+    about = "web_chat server and administration commands"
+)]
+pub struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    #[arg(
+        long = "env",
+        global = true,
+        help = "Environment to load: dev, test or prod"
+    )]
+    environment: Option<String>,
+
+    #[arg(long, global = true, help = "Directory with the TOML config files")]
+    config_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "set",
+        global = true,
+        value_name = "KEY=VALUE",
+        value_parser = parse_key_value,
+        help = "Override a config value, e.g. --set database.max_connections=10"
+    )]
+    values: Vec<(String, String)>,
+}
+
+impl From<ConfigArgs> for ConfigOverrides {
+    fn from(value: ConfigArgs) -> Self {
+        Self {
+            environment: value.environment,
+            config_dir: value.config_dir,
+            values: value.values,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Run the HTTP server (default)")]
+    Serve,
+
+    #[command(about = "Apply, revert or inspect database migrations")]
+    Migrate {
+        #[command(subcommand)]
+        command: migrate_command::MigrateCommand,
+    },
+
+    #[command(about = "Create a user with the admin role")]
+    CreateAdmin(user_command::CreateAdminArgs),
+
+    #[command(about = "Inspect the configuration")]
+    Config {
+        #[command(subcommand)]
+        command: config_command::ConfigCommand,
+    },
+
+    #[command(about = "Work with the OpenAPI document")]
+    Openapi {
+        #[command(subcommand)]
+        command: openapi_command::OpenapiCommand,
+    },
+
+    #[command(about = "Manage users")]
+    Users {
+        #[command(subcommand)]
+        command: user_command::UsersCommand,
+    },
+}
+
+impl Cli {
+    pub async fn run(self) -> AppResult<()> {
+        let command = self.command.unwrap_or(Command::Serve);
+        let overrides = ConfigOverrides::from(self.config);
+
+        // administration commands keep stdout for their own output
+        let level = match command {
+            Command::Serve => "info",
+            _ => "warn",
+        };
+        let _telemetry = telemetry::init_logger(level);
+
+        match command {
+            Command::Serve => crate::serve(overrides).await,
+            Command::Migrate { command } => migrate_command::run(command, overrides).await,
+            Command::CreateAdmin(args) => user_command::create_admin(args, overrides).await,
+            Command::Config { command } => config_command::run(command, overrides),
+            Command::Openapi { command } => openapi_command::run(command),
+            Command::Users { command } => user_command::run(command, overrides).await,
+        }
+    }
+}
+
+fn parse_key_value(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{value}`"))
+}
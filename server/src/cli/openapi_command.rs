@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use utoipa::OpenApi;
+
+use crate::core::{api_doc::ApiDoc, app_error::AppResult};
+
+#[derive(Subcommand)]
+pub enum OpenapiCommand {
+    #[command(about = "Write the OpenAPI document as JSON")]
+    Dump {
+        #[arg(long, short, default_value = "openapi.json", help = "Output file")]
+        output: PathBuf,
+    },
+}
+
+pub fn run(command: OpenapiCommand) -> AppResult<()> {
+    match command {
+        OpenapiCommand::Dump { output } => {
+            let json = ApiDoc::openapi().to_pretty_json()?;
+            std::fs::write(&output, json)?;
+
+            println!("OpenAPI document written to {}", output.display());
+            Ok(())
+        }
+    }
+}
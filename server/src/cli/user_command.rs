@@ -0,0 +1,106 @@
+use clap::{Args, Subcommand};
+use rand::distr::{Alphanumeric, SampleString};
+
+use crate::{
+    app::{models::users::ValidCreateUserRequest, services::user_service},
+    core::{
+        app_config::{AppConfig, ConfigOverrides},
+        app_error::AppResult,
+        database,
+    },
+};
+
+const GENERATED_PASSWORD_LENGTH: usize = 24;
+
+#[derive(Args)]
+pub struct CreateAdminArgs {
+    #[arg(long)]
+    email: String,
+
+    #[arg(
+        long,
+        help = "Password for the new admin; generated and printed when omitted"
+    )]
+    password: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum UsersCommand {
+    #[command(about = "List users with their roles")]
+    List {
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+    },
+
+    #[command(about = "Disable a user so they can no longer log in")]
+    Disable {
+        #[arg(long)]
+        email: String,
+    },
+}
+
+pub async fn create_admin(args: CreateAdminArgs, overrides: ConfigOverrides) -> AppResult<()> {
+    let config = AppConfig::configure(overrides)?;
+    let pool = database::connect(&config.database).await?;
+
+    let generated = args.password.is_none();
+    let password = args
+        .password
+        .unwrap_or_else(|| Alphanumeric.sample_string(&mut rand::rng(), GENERATED_PASSWORD_LENGTH));
+
+    let user = ValidCreateUserRequest {
+        email: args.email.try_into()?,
+        password: password.clone().try_into()?,
+    };
+
+    let user_id = user_service::create_admin(user, &pool).await?;
+
+    println!("created admin {user_id}");
+    if generated {
+        println!("generated password: {password}");
+    }
+
+    pool.close().await;
+    Ok(())
+}
+
+pub async fn run(command: UsersCommand, overrides: ConfigOverrides) -> AppResult<()> {
+    let config = AppConfig::configure(overrides)?;
+    let pool = database::connect(&config.database).await?;
+
+    match command {
+        UsersCommand::List { limit, offset } => {
+            let users = user_service::list_users(limit, offset, &pool).await?;
+
+            println!(
+                "{:<36}  {:<32}  {:<16}  {:<8}  CREATED AT",
+                "ID", "EMAIL", "ROLES", "STATUS"
+            );
+            for user in users {
+                let status = match user.disabled_at {
+                    Some(_) => "disabled",
+                    None => "active",
+                };
+                println!(
+                    "{:<36}  {:<32}  {:<16}  {:<8}  {}",
+                    user.id,
+                    user.email,
+                    user.roles.join(","),
+                    status,
+                    user.created_at.to_rfc3339()
+                );
+            }
+        }
+        UsersCommand::Disable { email } => {
+            let user_id = user_service::disable_user(&email, &pool).await?;
+
+            println!("disabled user {user_id}");
+        }
+    }
+
+    pool.close().await;
+    Ok(())
+}
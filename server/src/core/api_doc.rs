@@ -1,19 +1,62 @@
 use utoipa::OpenApi;
 
-use crate::app::controllers::user_controller;
+use crate::app::controllers::{
+    attachment_controller, health_controller, message_controller, metrics_controller,
+    presence_controller, profile_controller, reaction_controller, room_controller,
+    search_controller, user_controller, ws_controller,
+};
 
 #[derive(OpenApi)]
 #[openapi(
     info(
         title = "web_chat",
-        description = "WebChat API documentation", version = "0.1"
+        description = "WebChat API documentation",
+        version = "0.1"
     ),
     paths(
+        health_controller::live,
+        health_controller::ready,
+        metrics_controller::metrics,
         user_controller::get_user,
         user_controller::login_user,
         user_controller::create_user,
         user_controller::patch_user,
         user_controller::delete_user,
+        room_controller::create_room,
+        room_controller::list_rooms,
+        room_controller::join_room,
+        room_controller::leave_room,
+        room_controller::invite_user,
+        room_controller::list_unread,
+        room_controller::mark_read,
+        message_controller::send_message,
+        message_controller::list_messages,
+        message_controller::get_thread,
+        message_controller::list_mentions,
+        message_controller::edit_message,
+        message_controller::delete_message,
+        message_controller::list_revisions,
+        message_controller::pin_message,
+        message_controller::unpin_message,
+        message_controller::list_pins,
+        reaction_controller::add_reaction,
+        reaction_controller::remove_reaction,
+        presence_controller::set_presence,
+        presence_controller::list_room_presence,
+        profile_controller::get_profile,
+        profile_controller::set_avatar,
+        profile_controller::delete_avatar,
+        profile_controller::get_avatar,
+        search_controller::search_messages,
+        attachment_controller::upload_attachments,
+        attachment_controller::download_attachment,
+        attachment_controller::download_thumbnail,
+        attachment_controller::sign_attachment_url,
+        attachment_controller::sign_thumbnail_url,
+        attachment_controller::download_signed_attachment,
+        attachment_controller::download_signed_thumbnail,
+        ws_controller::create_ticket,
+        ws_controller::connect,
     )
 )]
 pub struct ApiDoc;
@@ -1,11 +1,41 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
+
+use chrono::Duration;
 use sqlx::PgPool;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{
+    app::realtime::hub::ChatHub,
+    core::{
+        app_config::UploadLimits,
+        app_error::{AppError, AppResult},
+        metrics::Metrics,
+        storage::StorageBackend,
+        unfurl::Unfurler,
+    },
+};
 
-use crate::core::app_error::{AppError, AppResult};
+const DEFAULT_JWT_TTL_HOURS: i64 = 24;
 
 #[derive(Clone)]
 pub struct AppData {
     pub pool: PgPool,
     pub jwt_secret: String,
+    pub jwt_ttl: Duration,
+    pub metrics: Metrics,
+    pub hub: ChatHub,
+    pub storage: Arc<dyn StorageBackend>,
+    pub upload_limits: UploadLimits,
+    // wakes the image worker after an upload
+    pub image_jobs: Arc<Notify>,
+    pub unfurler: Arc<Unfurler>,
+    // wakes the link preview worker after a message with new links
+    pub link_jobs: Arc<Notify>,
+    pub disabled_users: DisabledUsers,
 }
 
 impl AppData {
@@ -14,10 +44,37 @@ impl AppData {
     }
 }
 
+// kept by the disabled user listener so requests do not query the database for it
+#[derive(Clone, Default)]
+pub struct DisabledUsers(Arc<RwLock<HashSet<Uuid>>>);
+
+impl DisabledUsers {
+    pub fn contains(&self, user_id: &Uuid) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(user_id)
+    }
+
+    // true when the user was not known to be disabled yet
+    pub fn insert(&self, user_id: Uuid) -> bool {
+        self.0
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(user_id)
+    }
+}
+
 #[derive(Default)]
 pub struct AppDataBuilder {
     pool: Option<PgPool>,
     jwt_secret: Option<String>,
+    jwt_ttl: Option<Duration>,
+    metrics: Option<Metrics>,
+    hub: Option<ChatHub>,
+    storage: Option<Arc<dyn StorageBackend>>,
+    upload_limits: Option<UploadLimits>,
+    unfurler: Option<Unfurler>,
 }
 
 impl AppDataBuilder {
@@ -25,6 +82,17 @@ impl AppDataBuilder {
         let app_data = AppData {
             pool: self.pool.ok_or(AppError::MissingDatabasePool)?,
             jwt_secret: self.jwt_secret.ok_or(AppError::MissingJwtSecret)?,
+            jwt_ttl: self
+                .jwt_ttl
+                .unwrap_or(Duration::hours(DEFAULT_JWT_TTL_HOURS)),
+            metrics: self.metrics.ok_or(AppError::MissingMetrics)?,
+            hub: self.hub.unwrap_or_default(),
+            storage: self.storage.ok_or(AppError::MissingStorage)?,
+            upload_limits: self.upload_limits.unwrap_or_default(),
+            image_jobs: Arc::default(),
+            unfurler: Arc::new(self.unfurler.unwrap_or_default()),
+            link_jobs: Arc::default(),
+            disabled_users: DisabledUsers::default(),
         };
 
         Ok(app_data)
@@ -39,4 +107,34 @@ impl AppDataBuilder {
         self.jwt_secret = Some(secret);
         self
     }
+
+    pub fn with_jwt_ttl(mut self, ttl: Duration) -> Self {
+        self.jwt_ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn with_hub(mut self, hub: ChatHub) -> Self {
+        self.hub = Some(hub);
+        self
+    }
+
+    pub fn with_storage(mut self, storage: Arc<dyn StorageBackend>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    pub fn with_upload_limits(mut self, limits: UploadLimits) -> Self {
+        self.upload_limits = Some(limits);
+        self
+    }
+
+    pub fn with_unfurler(mut self, unfurler: Unfurler) -> Self {
+        self.unfurler = Some(unfurler);
+        self
+    }
 }
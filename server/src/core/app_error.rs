@@ -1,3 +1,5 @@
+use crate::{app::request_error::RequestError, core::storage::StorageError};
+
 pub type AppResult<T> = Result<T, AppError>;
 
 #[derive(Debug, thiserror::Error)]
@@ -17,6 +19,9 @@ pub enum AppError {
     #[error("ConfigError. Context: {0}")]
     ConfigError(#[from] config::ConfigError),
 
+    #[error("InvalidConfig. Context: {}", .0.join("; "))]
+    InvalidConfig(Vec<String>),
+
     #[error("ParseBoolError. Context: {0}")]
     ParseBoolError(#[from] std::str::ParseBoolError),
 
@@ -26,12 +31,36 @@ pub enum AppError {
     #[error("ParseFloatError. Context: {0}")]
     ParseFloatError(#[from] std::num::ParseFloatError),
 
+    #[error("PrometheusError. Context: {0}")]
+    PrometheusError(#[from] prometheus::Error),
+
+    #[error("SerdeJsonError. Context: {0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("TlsError. Context: {0}")]
+    TlsError(#[from] rustls::Error),
+
+    #[error("PemError. Context: {0}")]
+    PemError(#[from] rustls::pki_types::pem::Error),
+
+    #[error("RequestError. Context: {0}")]
+    RequestError(#[from] RequestError),
+
+    #[error("StorageError. Context: {0}")]
+    StorageError(#[from] StorageError),
+
     #[error("Missing database pool field in AppData")]
     MissingDatabasePool,
 
     #[error("Missing JWT secret field in AppData")]
     MissingJwtSecret,
 
+    #[error("Missing metrics field in AppData")]
+    MissingMetrics,
+
+    #[error("Missing storage field in AppData")]
+    MissingStorage,
+
     #[error("OtherError. Context: {0}")]
     Other(String),
 }
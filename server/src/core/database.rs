@@ -1,26 +1,53 @@
-use sqlx::{
-    PgPool,
-    postgres::{PgConnectOptions, PgPoolOptions},
-};
+use std::time::Duration;
 
-use crate::core::app_error::AppResult;
+use sqlx::{PgPool, migrate::Migrator};
 
-pub async fn connect(options: PgConnectOptions) -> AppResult<PgPool> {
+use crate::core::{app_config::DatabaseSettings, app_error::AppResult};
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(10);
+
+pub async fn connect(settings: &DatabaseSettings) -> AppResult<PgPool> {
     tracing::info!("establishing database connection");
 
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect_with(options)
-        .await?;
+    let options = settings.options()?;
+    let mut backoff = settings.connect_backoff();
+    let mut attempt = 0;
+
+    loop {
+        match settings.pool_options().connect_with(options.clone()).await {
+            Ok(pool) => return Ok(pool),
+            Err(e) if attempt < settings.connect_retries && is_transient(&e) => {
+                attempt += 1;
+                tracing::warn!(
+                    "database connection failed (attempt {}/{}): {}; retrying in {:?}",
+                    attempt,
+                    settings.connect_retries,
+                    e,
+                    backoff
+                );
 
-    let is_run_migrate: bool = std::env::var("MIGRATE_RUN")
-        .unwrap_or("false".into())
-        .parse()?;
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
 
-    if is_run_migrate {
-        tracing::info!("running migrations");
-        sqlx::migrate!("./migrations").run(&pool).await?;
+// errors worth waiting out while Postgres is still starting or unreachable
+fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(db_error) => db_error.code().as_deref() == Some("57P03"),
+        _ => false,
     }
+}
+
+pub async fn migrate(pool: &PgPool) -> AppResult<()> {
+    tracing::info!("running migrations");
+    MIGRATOR.run(pool).await?;
 
-    Ok(pool)
+    Ok(())
 }
@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use actix_ws::CloseCode;
+use sqlx::postgres::{PgListener, PgPoolOptions};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        request_error::RequestResult,
+        services::{attachment_service, link_preview_service, message_service, user_service},
+    },
+    core::{app_config::MessageSettings, app_data::AppData},
+};
+
+// uploads wake the worker right away, the poll picks up anything left after a failure
+const IMAGE_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const LINK_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const LISTENER_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const DISABLED_REASON: &str = "User is disabled";
+
+// the pool is closed only after every job has seen the shutdown and returned
+#[derive(Default)]
+pub struct Jobs {
+    shutdown: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Jobs {
+    fn spawn<F>(&self, job: impl FnOnce(CancellationToken) -> F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tracker.spawn(job(self.shutdown.clone()));
+    }
+
+    pub async fn shutdown(self) {
+        self.shutdown.cancel();
+        self.tracker.close();
+        self.tracker.wait().await;
+    }
+}
+
+pub fn spawn_message_purge(jobs: &Jobs, app_data: AppData, settings: MessageSettings) {
+    let Some(purge_interval) = settings.purge_interval() else {
+        return;
+    };
+
+    jobs.spawn(|shutdown| async move {
+        let mut interval = tokio::time::interval(purge_interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+
+            let retention = settings.deleted_retention();
+            match message_service::purge_deleted(retention, &app_data.pool).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!("purged the content of {} deleted message(s)", purged),
+                Err(e) => tracing::warn!("failed to purge deleted messages: {}", e),
+            }
+
+            let deleted = attachment_service::delete_discarded_objects(
+                &app_data.pool,
+                app_data.storage.as_ref(),
+            )
+            .await;
+            match deleted {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("deleted {} discarded object(s)", deleted),
+                Err(e) => tracing::warn!("failed to delete discarded objects: {}", e),
+            }
+        }
+    });
+}
+
+pub fn spawn_image_worker(jobs: &Jobs, app_data: AppData) {
+    jobs.spawn(|shutdown| async move {
+        let mut interval = tokio::time::interval(IMAGE_POLL_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = app_data.image_jobs.notified() => {}
+                _ = shutdown.cancelled() => break,
+            }
+
+            let processed = attachment_service::process_pending_images(
+                &app_data.pool,
+                app_data.storage.as_ref(),
+                &app_data.hub,
+            )
+            .await;
+
+            match processed {
+                Ok(0) => {}
+                Ok(processed) => tracing::info!("processed {} image attachment(s)", processed),
+                Err(e) => tracing::warn!("failed to process image attachments: {}", e),
+            }
+        }
+    });
+}
+
+pub fn spawn_link_preview_worker(jobs: &Jobs, app_data: AppData) {
+    jobs.spawn(|shutdown| async move {
+        let mut interval = tokio::time::interval(LINK_POLL_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = app_data.link_jobs.notified() => {}
+                _ = shutdown.cancelled() => break,
+            }
+
+            let processed = link_preview_service::process_pending_links(
+                &app_data.pool,
+                &app_data.unfurler,
+                &app_data.hub,
+            )
+            .await;
+
+            match processed {
+                Ok(0) => {}
+                Ok(processed) => tracing::info!("fetched {} link preview(s)", processed),
+                Err(e) => tracing::warn!("failed to fetch link previews: {}", e),
+            }
+        }
+    });
+}
+
+// users are disabled from the CLI, which cannot reach the sessions of this process
+pub fn spawn_user_disabled_listener(jobs: &Jobs, app_data: AppData) {
+    // LISTEN holds its connection for good, so it gets one outside of the request pool
+    let listener_pool = PgPoolOptions::new()
+        .max_connections(1)
+        .max_lifetime(None)
+        .idle_timeout(None)
+        .connect_lazy_with((*app_data.pool.connect_options()).clone());
+
+    jobs.spawn(|shutdown| async move {
+        'connect: loop {
+            let listener = match PgListener::connect_with(&listener_pool).await {
+                Ok(mut listener) => listener
+                    .listen(user_service::USER_DISABLED_CHANNEL)
+                    .await
+                    .map(|_| listener),
+                Err(e) => Err(e),
+            };
+            let mut listener = match listener {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::warn!("failed to listen for disabled users: {}", e);
+                    match retry_later(&shutdown).await {
+                        true => continue,
+                        false => break,
+                    }
+                }
+            };
+
+            // catches up on users disabled while nobody was listening
+            if let Err(e) = sync_disabled_users(&app_data).await {
+                tracing::warn!("failed to load disabled users: {}", e);
+                match retry_later(&shutdown).await {
+                    true => continue,
+                    false => break,
+                }
+            }
+
+            loop {
+                let notification = tokio::select! {
+                    _ = shutdown.cancelled() => break 'connect,
+                    notification = listener.try_recv() => notification,
+                };
+                // notifications sent until the next LISTEN are lost, so reconnect and sync again
+                let notification = match notification {
+                    Ok(Some(notification)) => notification,
+                    Ok(None) => continue 'connect,
+                    Err(e) => {
+                        tracing::warn!("failed to receive disabled users: {}", e);
+                        match retry_later(&shutdown).await {
+                            true => continue 'connect,
+                            false => break 'connect,
+                        }
+                    }
+                };
+
+                match notification.payload().parse::<Uuid>() {
+                    Ok(user_id) => disable_user(&app_data, user_id),
+                    Err(e) => tracing::warn!("invalid disabled user notification: {}", e),
+                }
+            }
+        }
+
+        listener_pool.close().await;
+    });
+}
+
+// false when the server shuts down before it is time to retry
+async fn retry_later(shutdown: &CancellationToken) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(LISTENER_RETRY_INTERVAL) => true,
+        _ = shutdown.cancelled() => false,
+    }
+}
+
+// fills the set `verify_jwt` checks, before serving and after every reconnect of the listener
+pub async fn sync_disabled_users(app_data: &AppData) -> RequestResult<()> {
+    for user_id in user_service::list_disabled_users(&app_data.pool).await? {
+        disable_user(app_data, user_id);
+    }
+
+    Ok(())
+}
+
+fn disable_user(app_data: &AppData, user_id: Uuid) {
+    if !app_data.disabled_users.insert(user_id) {
+        return;
+    }
+
+    // 1008 (policy violation) tells clients not to reconnect on their own
+    let hub = &app_data.hub;
+    let closed = hub.close_user(user_id, CloseCode::Policy, DISABLED_REASON);
+    if closed > 0 {
+        tracing::info!(%user_id, "closed {} session(s) of a disabled user", closed);
+    }
+}
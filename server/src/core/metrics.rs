@@ -0,0 +1,109 @@
+use std::sync::LazyLock;
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+use crate::core::app_error::{AppError, AppResult};
+
+// observed by `telemetry::PoolAcquireLayer` from the event sqlx emits after every acquire,
+// so it lives outside of `Metrics` which is created after the logger
+pub static DB_POOL_ACQUIRE_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    let opts = HistogramOpts::new(
+        "db_pool_acquire_duration_seconds",
+        "Time spent waiting for a database connection in seconds",
+    );
+    Histogram::with_opts(opts).expect("histogram options are valid")
+});
+
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub db_pool_connections: IntGaugeVec,
+    pub db_pool_acquire_duration: Histogram,
+    pub ws_connections: IntGauge,
+    pub messages_sent: IntCounter,
+    pub login_attempts: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> AppResult<Self> {
+        let registry = Registry::new_custom(Some("web_chat".into()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Total number of HTTP requests"),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route"],
+        )?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )?;
+        let db_pool_acquire_duration = DB_POOL_ACQUIRE_DURATION.clone();
+        let ws_connections = IntGauge::new(
+            "ws_connections_active",
+            "Number of active WebSocket connections",
+        )?;
+        let messages_sent =
+            IntCounter::new("messages_sent_total", "Total number of chat messages sent")?;
+        let login_attempts = IntCounterVec::new(
+            Opts::new("login_attempts_total", "Login attempts by result"),
+            &["result"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(db_pool_acquire_duration.clone()))?;
+        registry.register(Box::new(ws_connections.clone()))?;
+        registry.register(Box::new(messages_sent.clone()))?;
+        registry.register(Box::new(login_attempts.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_pool_connections,
+            db_pool_acquire_duration,
+            ws_connections,
+            messages_sent,
+            login_attempts,
+        })
+    }
+
+    // read from the pool's counters, a scrape never takes a connection
+    pub fn observe_pool(&self, pool: &PgPool) {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+
+        self.db_pool_connections
+            .with_label_values(&["max"])
+            .set(pool.options().get_max_connections() as i64);
+        self.db_pool_connections
+            .with_label_values(&["size"])
+            .set(size);
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+        self.db_pool_connections
+            .with_label_values(&["in_use"])
+            .set(size - idle);
+    }
+
+    pub fn encode(&self) -> AppResult<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        String::from_utf8(buffer).map_err(|e| AppError::Other(e.to_string()))
+    }
+}
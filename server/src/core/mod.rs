@@ -3,5 +3,10 @@ pub mod app_config;
 pub mod app_data;
 pub mod app_error;
 pub mod database;
+pub mod jobs;
+pub mod metrics;
 pub mod server;
+pub mod storage;
 pub mod telemetry;
+pub mod tls;
+pub mod unfurl;
@@ -1,27 +1,162 @@
-use actix_cors::Cors;
-use actix_web::{App, HttpServer, web};
-use std::net::TcpListener;
+use actix_governor::Governor;
+use actix_web::{
+    App, HttpServer,
+    dev::Server,
+    middleware::{Condition, from_fn},
+    web,
+};
+use std::{net::TcpListener, sync::Arc};
 use tracing_actix_web::TracingLogger;
 
 use crate::{
-    app::routers::{swagger_router, user_router},
-    core::{app_data::AppData, app_error::AppResult},
+    app::{
+        middlewares::{metrics, request_id},
+        routers::{
+            attachment_router, health_router, message_router, metrics_router, presence_router,
+            profile_router, reaction_router, room_router, search_router, swagger_router,
+            user_router, ws_router,
+        },
+    },
+    core::{
+        app_config::AppConfig,
+        app_data::AppData,
+        app_error::{AppError, AppResult},
+        telemetry::PathOnlyRootSpanBuilder,
+        tls::{self, CertificateResolver},
+    },
 };
 
-pub async fn run(lst: TcpListener, app_data: AppData) -> AppResult<()> {
+const SHUTDOWN_REASON: &str = "server restarting";
+
+pub async fn run(lst: TcpListener, app_data: AppData, config: &AppConfig) -> AppResult<()> {
     tracing::info!("running server");
 
-    HttpServer::new(move || {
+    let cors = config.cors.clone();
+    let rate_limit_enabled = config.rate_limit.enabled;
+    let governor = config
+        .rate_limit
+        .governor()
+        .ok_or(AppError::InvalidConfig(vec![
+            "rate_limit: invalid quota".into(),
+        ]))?;
+
+    let hub = app_data.hub.clone();
+    let metrics = metrics_server(config.metrics.addr(), app_data.clone())?;
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(Cors::permissive())
-            .wrap(TracingLogger::default())
+            .wrap(cors.cors())
+            .wrap(from_fn(metrics::record_metrics))
+            .wrap(from_fn(request_id::propagate_request_id))
+            .wrap(TracingLogger::<PathOnlyRootSpanBuilder>::new())
             .app_data(web::Data::new(app_data.clone()))
-            .configure(swagger_router::configure)
-            .configure(user_router::configure)
+            // probes come often from a few addresses and must not be turned away
+            .configure(health_router::configure)
+            .service(
+                web::scope("")
+                    .wrap(Condition::new(rate_limit_enabled, Governor::new(&governor)))
+                    .configure(swagger_router::configure)
+                    .configure(user_router::configure)
+                    .configure(room_router::configure)
+                    .configure(message_router::configure)
+                    .configure(reaction_router::configure)
+                    .configure(presence_router::configure)
+                    .configure(profile_router::configure)
+                    .configure(search_router::configure)
+                    .configure(attachment_router::configure)
+                    .configure(ws_router::configure),
+            )
     })
-    .listen(lst)?
-    .run()
-    .await?;
+    .shutdown_timeout(config.app.shutdown_timeout().as_secs())
+    .disable_signals();
+
+    let server = if config.tls.enabled {
+        let resolver = Arc::new(CertificateResolver::load(&config.tls)?);
+        tls::watch_certificates(resolver.clone(), config.tls.clone());
+
+        server.listen_rustls_0_23(lst, tls::server_config(resolver)?)?
+    } else {
+        server.listen(lst)?
+    }
+    .run();
+
+    let redirect = if config.tls.redirect_http {
+        let addr = config.tls.redirect_addr(&config.app);
+        Some(tls::redirect_server(addr, config.app.port())?)
+    } else {
+        None
+    };
+
+    let handle = server.handle();
+    let redirect_handle = redirect.as_ref().map(|redirect| redirect.handle());
+    let metrics_handle = metrics.handle();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        tracing::info!("shutdown signal received, draining in-flight requests");
+
+        // clients reconnect to another instance on close code 1012 (service restart)
+        hub.close_all(SHUTDOWN_REASON);
+
+        if let Some(redirect_handle) = redirect_handle {
+            redirect_handle.stop(true).await;
+        }
+        handle.stop(true).await;
+        metrics_handle.stop(true).await;
+    });
+
+    let redirect = async {
+        match redirect {
+            Some(redirect) => redirect.await,
+            None => Ok(()),
+        }
+    };
+    tokio::try_join!(server, redirect, metrics)?;
 
     Ok(())
 }
+
+// scrapes do not go through the public listener, its CORS or its rate limit
+fn metrics_server(addr: String, app_data: AppData) -> AppResult<Server> {
+    tracing::info!("serving metrics on {}", addr);
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(app_data.clone()))
+            .configure(metrics_router::configure)
+    })
+    .workers(1)
+    .disable_signals()
+    .bind(addr)?
+    .run();
+
+    Ok(server)
+}
+
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::core::storage::{StorageBackend, StorageError, StorageResult};
+
+// stores every object as a file below `root`
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        // keys come from the server, this only guards against mistakes
+        key.split('/')
+            .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+            .fold(self.root.clone(), |path, part| path.join(part))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    async fn put(&self, key: &str, _content_type: &str, data: Bytes) -> StorageResult<()> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // readers never see a partially written file
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, &data).await?;
+        tokio::fs::rename(&partial, &path).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> StorageResult<Bytes> {
+        match tokio::fs::read(self.path(key)).await {
+            Ok(data) => Ok(data.into()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(StorageError::NotFound(key.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_storage() {
+        let root = std::env::temp_dir().join(format!("web_chat_{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(&root);
+
+        storage
+            .put("room/../file", "text/plain", Bytes::from_static(b"hello"))
+            .await
+            .unwrap();
+        assert!(root.join("room/file").is_file());
+        assert_eq!(storage.get("room/file").await.unwrap(), "hello");
+
+        storage.delete("room/file").await.unwrap();
+        storage.delete("room/file").await.unwrap();
+        assert!(matches!(
+            storage.get("room/file").await,
+            Err(StorageError::NotFound(_))
+        ));
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
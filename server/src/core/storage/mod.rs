@@ -0,0 +1,52 @@
+pub mod local;
+pub mod s3;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::core::{
+    app_config::{AttachmentSettings, StorageKind},
+    app_error::AppResult,
+};
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("Object `{0}` not found")]
+    NotFound(String),
+
+    #[error("IoError. Context: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("HttpError. Context: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Storage responded with {status}. Context: {body}")]
+    UnexpectedStatus { status: u16, body: String },
+}
+
+// where attachment contents live; keys are `/`-separated and generated by the server
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, data: Bytes) -> StorageResult<()>;
+
+    async fn get(&self, key: &str) -> StorageResult<Bytes>;
+
+    // deleting a missing object is not an error
+    async fn delete(&self, key: &str) -> StorageResult<()>;
+}
+
+pub fn from_settings(settings: &AttachmentSettings) -> AppResult<Arc<dyn StorageBackend>> {
+    let storage: Arc<dyn StorageBackend> = match settings.storage {
+        StorageKind::Local => Arc::new(LocalStorage::new(&settings.local_path)),
+        StorageKind::S3 => Arc::new(S3Storage::new(&settings.s3)?),
+    };
+
+    Ok(storage)
+}
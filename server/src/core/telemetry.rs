@@ -1,9 +1,49 @@
-use tracing::Level;
+use actix_web::{
+    Error, HttpMessage,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::HeaderMap,
+};
+use opentelemetry::{
+    global,
+    propagation::Extractor,
+    trace::{TraceContextExt, TracerProvider},
+};
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing::{
+    Event, Level, Metadata, Span, Subscriber,
+    field::{Empty, Field, Visit},
+};
+use tracing_actix_web::{DefaultRootSpanBuilder, RequestId, RootSpanBuilder};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::{
-    EnvFilter, Layer, Registry, filter::filter_fn, layer::SubscriberExt, util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
+    filter::filter_fn,
+    layer::{Context, SubscriberExt},
+    util::SubscriberInitExt,
 };
 
-pub fn init_logger<S>(level: S)
+use crate::core::metrics::DB_POOL_ACQUIRE_DURATION;
+
+const DEFAULT_SERVICE_NAME: &str = "web_chat";
+const POOL_ACQUIRE_TARGET: &str = "sqlx::pool::acquire";
+
+pub struct TelemetryGuard {
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.tracer_provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("failed to shut down tracer provider: {e}");
+        }
+    }
+}
+
+pub fn init_logger<S>(level: S) -> TelemetryGuard
 where
     S: AsRef<str>,
 {
@@ -17,6 +57,7 @@ where
         .with_writer(std::io::stdout)
         .with_filter(filter_fn(|meta| {
             matches!(*meta.level(), Level::TRACE | Level::DEBUG | Level::INFO)
+                && !is_pool_acquire(meta)
         }));
 
     let stderr_layer = tracing_subscriber::fmt::layer()
@@ -28,14 +69,279 @@ where
         .with_span_list(true)
         .with_writer(std::io::stderr)
         .with_filter(filter_fn(|meta| {
-            matches!(*meta.level(), Level::WARN | Level::ERROR)
+            // spans must pass so error lines carry their context (e.g. request_id)
+            meta.is_span() || matches!(*meta.level(), Level::WARN | Level::ERROR)
         }));
 
-    let env_layer = EnvFilter::try_from_default_env().unwrap_or(level.as_ref().into());
+    // acquires that are not slow are only for `PoolAcquireLayer`, whatever the log level
+    let env_layer = EnvFilter::try_from_default_env()
+        .unwrap_or(level.as_ref().into())
+        .add_directive(
+            format!("{POOL_ACQUIRE_TARGET}=trace")
+                .parse()
+                .expect("directive is valid"),
+        );
+
+    // the exporter is enabled only when a collector endpoint is configured
+    let tracer_provider = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .map(|endpoint| {
+            let service_name =
+                std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.into());
+
+            build_tracer_provider(&endpoint, service_name)
+        });
+
+    let (tracer_provider, exporter_error) = match tracer_provider {
+        Some(Ok(provider)) => (Some(provider), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+
+    let otel_layer = tracer_provider.as_ref().map(|provider| {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(DEFAULT_SERVICE_NAME))
+            .with_filter(filter_fn(|meta| !is_pool_acquire(meta)))
+    });
 
     Registry::default()
         .with(stdout_layer)
         .with(stderr_layer)
+        .with(otel_layer)
+        .with(PoolAcquireLayer)
         .with(env_layer)
         .init();
+
+    if let Some(e) = exporter_error {
+        tracing::warn!("OTLP exporter is disabled: {}", e);
+    }
+
+    TelemetryGuard { tracer_provider }
+}
+
+// sqlx reports the wait of every acquire, at WARN when it was slow and at TRACE otherwise
+fn is_pool_acquire(meta: &Metadata) -> bool {
+    meta.target() == POOL_ACQUIRE_TARGET && *meta.level() == Level::TRACE
+}
+
+struct PoolAcquireLayer;
+
+impl<S: Subscriber> Layer<S> for PoolAcquireLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != POOL_ACQUIRE_TARGET {
+            return;
+        }
+
+        let mut acquired_after = AcquiredAfter(None);
+        event.record(&mut acquired_after);
+        if let Some(secs) = acquired_after.0 {
+            DB_POOL_ACQUIRE_DURATION.observe(secs);
+        }
+    }
+}
+
+struct AcquiredAfter(Option<f64>);
+
+impl Visit for AcquiredAfter {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        // sic, the field name sqlx uses
+        if field.name() == "aquired_after_secs" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+pub fn build_tracer_provider(
+    endpoint: &str,
+    service_name: String,
+) -> Result<SdkTracerProvider, ExporterBuildError> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+
+    let resource = Resource::builder().with_service_name(service_name).build();
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
+
+// the fields of `DefaultRootSpanBuilder`, except that `http.target` is the path alone:
+// a query string can carry a WebSocket ticket and must not reach the logs or the collector
+pub struct PathOnlyRootSpanBuilder;
+
+impl RootSpanBuilder for PathOnlyRootSpanBuilder {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let http_method = request.method().as_str();
+        let http_route = request.match_pattern().unwrap_or_else(|| "default".into());
+        let user_agent = request
+            .headers()
+            .get("User-Agent")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        let request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let connection_info = request.connection_info();
+
+        let span = tracing::info_span!(
+            "HTTP request",
+            http.method = %http_method,
+            http.route = %http_route,
+            http.flavor = ?request.version(),
+            http.scheme = %connection_info.scheme(),
+            http.host = %connection_info.host(),
+            http.client_ip = %connection_info.realip_remote_addr().unwrap_or(""),
+            http.user_agent = %user_agent,
+            http.target = %request.path(),
+            http.status_code = Empty,
+            otel.name = %format!("{http_method} {http_route}"),
+            otel.kind = "server",
+            otel.status_code = Empty,
+            trace_id = Empty,
+            request_id = %request_id,
+            exception.message = Empty,
+            exception.details = Empty,
+        );
+
+        // continues the trace of the caller, as the default builder does
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent);
+        let trace_id = span.context().span().span_context().trace_id();
+        span.record(
+            "trace_id",
+            tracing::field::display(format!("{trace_id:032x}")),
+        );
+
+        span
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex, mpsc},
+        thread,
+        time::Duration,
+    };
+
+    use actix_web::{App, HttpResponse, test::TestRequest, web};
+    use expect_test::expect;
+    use tracing_actix_web::TracingLogger;
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    use super::*;
+
+    #[test]
+    fn test_spans_exported_to_collector() {
+        // stand-in collector that records the first request line it receives
+        let lst = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", lst.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = lst.accept().unwrap();
+            let mut buffer = [0; 8192];
+            let n = stream.read(&mut buffer).unwrap();
+            let request = String::from_utf8_lossy(&buffer[..n]).to_string();
+
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            tx.send(request.lines().next().unwrap_or_default().to_owned())
+                .unwrap();
+        });
+
+        let provider = build_tracer_provider(&endpoint, "test".into()).unwrap();
+        let subscriber = Registry::default()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("test_span").in_scope(|| tracing::info!("inside span"));
+        });
+        provider.force_flush().unwrap();
+
+        let request_line = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let exp = expect!["POST /v1/traces HTTP/1.1"];
+        exp.assert_eq(&request_line);
+    }
+
+    #[test]
+    fn test_pool_acquire_observed() {
+        let before = DB_POOL_ACQUIRE_DURATION.get_sample_count();
+        let subscriber = Registry::default().with(PoolAcquireLayer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::trace!(target: "sqlx::pool::acquire", aquired_after_secs = 0.25, "acquired connection");
+            tracing::warn!(target: "sqlx::pool::acquire", aquired_after_secs = 3.0, "acquired connection, but time to acquire exceeded slow threshold");
+            tracing::trace!(target: "sqlx::query", elapsed_secs = 0.5, "summary");
+        });
+
+        assert_eq!(DB_POOL_ACQUIRE_DURATION.get_sample_count() - before, 2);
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_root_span_omits_query() {
+        let buffer = SharedBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = actix_web::test::init_service(
+            App::new()
+                .wrap(TracingLogger::<PathOnlyRootSpanBuilder>::new())
+                .route("/ws", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::get().uri("/ws?ticket=secret").to_request();
+        actix_web::test::call_service(&app, req).await;
+
+        let logs = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains(r#""http.target":"/ws""#), "{logs}");
+        assert!(!logs.contains("secret"), "{logs}");
+    }
 }
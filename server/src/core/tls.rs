@@ -0,0 +1,237 @@
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use actix_web::{
+    App, HttpRequest, HttpResponse, HttpServer,
+    dev::Server,
+    http::{header, uri::Authority},
+    web,
+};
+use rustls::{
+    ServerConfig,
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use tracing_actix_web::TracingLogger;
+
+use crate::core::{
+    app_config::TlsSettings,
+    app_error::{AppError, AppResult},
+    telemetry::PathOnlyRootSpanBuilder,
+};
+
+// hands out the current certificate, swapped in place when the files change
+#[derive(Debug)]
+pub struct CertificateResolver {
+    key: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertificateResolver {
+    pub fn load(settings: &TlsSettings) -> AppResult<Self> {
+        let key = load_certified_key(&settings.cert_path, &settings.key_path)?;
+
+        Ok(Self {
+            key: RwLock::new(Arc::new(key)),
+        })
+    }
+
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        self.key.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn replace(&self, key: CertifiedKey) {
+        *self.key.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(key);
+    }
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+pub fn server_config(resolver: Arc<CertificateResolver>) -> AppResult<ServerConfig> {
+    let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+
+    Ok(config)
+}
+
+pub fn load_certified_key(cert_path: &Path, key_path: &Path) -> AppResult<CertifiedKey> {
+    let certs = CertificateDer::pem_file_iter(cert_path)?.collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(AppError::Other(format!(
+            "no certificates found in {}",
+            cert_path.display()
+        )));
+    }
+
+    let key = PrivateKeyDer::from_pem_file(key_path)?;
+
+    Ok(CertifiedKey::from_der(
+        certs,
+        key,
+        &ring::default_provider(),
+    )?)
+}
+
+pub fn watch_certificates(resolver: Arc<CertificateResolver>, settings: TlsSettings) {
+    let Some(reload_interval) = settings.reload_interval() else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut last_modified = modified(&settings);
+        let mut interval = tokio::time::interval(reload_interval);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            let current = modified(&settings);
+            if current == last_modified {
+                continue;
+            }
+
+            // a failed reload keeps the old certificate and is retried on the next tick,
+            // which covers the certificate and key being replaced one after another
+            match load_certified_key(&settings.cert_path, &settings.key_path) {
+                Ok(key) => {
+                    resolver.replace(key);
+                    last_modified = current;
+                    tracing::info!("reloaded TLS certificate");
+                }
+                Err(e) => tracing::warn!("failed to reload TLS certificate: {}", e),
+            }
+        }
+    });
+}
+
+fn modified(settings: &TlsSettings) -> Option<(SystemTime, SystemTime)> {
+    let cert = std::fs::metadata(&settings.cert_path)
+        .ok()?
+        .modified()
+        .ok()?;
+    let key = std::fs::metadata(&settings.key_path)
+        .ok()?
+        .modified()
+        .ok()?;
+
+    Some((cert, key))
+}
+
+pub fn redirect_server(addr: String, https_port: u16) -> AppResult<Server> {
+    tracing::info!("redirecting plain HTTP on {} to HTTPS", addr);
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .wrap(TracingLogger::<PathOnlyRootSpanBuilder>::new())
+            .app_data(web::Data::new(https_port))
+            .default_service(web::to(redirect_to_https))
+    })
+    .workers(1)
+    .disable_signals()
+    .bind(addr)?
+    .run();
+
+    Ok(server)
+}
+
+async fn redirect_to_https(req: HttpRequest, https_port: web::Data<u16>) -> HttpResponse {
+    let host = {
+        let info = req.connection_info();
+        match info.host().parse::<Authority>() {
+            Ok(authority) => authority.host().to_owned(),
+            Err(_) => return HttpResponse::BadRequest().finish(),
+        }
+    };
+
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+
+    let location = match **https_port {
+        443 => format!("https://{host}{path}"),
+        port => format!("https://{host}:{port}{path}"),
+    };
+
+    HttpResponse::PermanentRedirect()
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::{TestRequest, call_service, init_service};
+    use rcgen::{CertifiedKey as GeneratedKey, generate_simple_self_signed};
+
+    use super::*;
+
+    fn write_certificate(dir: &Path, name: &str) -> GeneratedKey<rcgen::KeyPair> {
+        let generated = generate_simple_self_signed(vec![name.to_owned()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), generated.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), generated.signing_key.serialize_pem()).unwrap();
+        generated
+    }
+
+    #[test]
+    fn test_certificate_reload() {
+        let dir = std::env::temp_dir().join(format!("web_chat_tls_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert_path, key_path) = (dir.join("cert.pem"), dir.join("key.pem"));
+
+        let first = write_certificate(&dir, "first.localhost");
+        let resolver = CertificateResolver {
+            key: RwLock::new(Arc::new(load_certified_key(&cert_path, &key_path).unwrap())),
+        };
+        assert_eq!(
+            resolver.current().end_entity_cert().unwrap(),
+            first.cert.der()
+        );
+
+        let second = write_certificate(&dir, "second.localhost");
+        resolver.replace(load_certified_key(&cert_path, &key_path).unwrap());
+        assert_eq!(
+            resolver.current().end_entity_cert().unwrap(),
+            second.cert.der()
+        );
+
+        // a key that does not belong to the certificate is rejected
+        let other = generate_simple_self_signed(vec!["other.localhost".into()]).unwrap();
+        std::fs::write(&key_path, other.signing_key.serialize_pem()).unwrap();
+        assert!(load_certified_key(&cert_path, &key_path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_redirect_to_https() {
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(8443u16))
+                .default_service(web::to(redirect_to_https)),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/api/users/login?next=%2F")
+            .insert_header((header::HOST, "chat.example.com:8080"))
+            .to_request();
+        let res = call_service(&app, req).await;
+
+        assert_eq!(res.status(), 308);
+        assert_eq!(
+            res.headers().get(header::LOCATION).unwrap(),
+            "https://chat.example.com:8443/api/users/login?next=%2F"
+        );
+    }
+}
@@ -0,0 +1,335 @@
+mod opengraph;
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use reqwest::{
+    Client, StatusCode, Url,
+    header::{ACCEPT, CONTENT_TYPE, LOCATION},
+    redirect::Policy,
+};
+use thiserror::Error;
+
+use crate::core::app_config::LinkPreviewSettings;
+
+pub use opengraph::LinkMetadata;
+
+const USER_AGENT: &str = concat!("web_chat-link-preview/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_BYTES: usize = 1024 * 1024;
+const DEFAULT_MAX_REDIRECTS: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum UnfurlError {
+    #[error("Only http and https links can be previewed: {0}")]
+    UnsupportedUrl(String),
+    #[error("Address {0} is not publicly routable")]
+    BlockedAddress(IpAddr),
+    #[error("Cannot resolve host: {0}")]
+    ResolveError(#[from] std::io::Error),
+    #[error("Http error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Unexpected status {0}")]
+    UnexpectedStatus(StatusCode),
+    #[error("Not an HTML page: {0}")]
+    NotHtml(String),
+    #[error("More than {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("Timed out")]
+    Timeout,
+}
+
+pub type UnfurlResult<T> = Result<T, UnfurlError>;
+
+// fetches the OpenGraph metadata of a page; every host is resolved up front and the
+// request pinned to the checked addresses, so DNS rebinding cannot reach a private one
+#[derive(Debug, Clone)]
+pub struct Unfurler {
+    timeout: Duration,
+    max_bytes: usize,
+    max_redirects: usize,
+    allow_private_networks: bool,
+}
+
+impl Unfurler {
+    pub fn new(settings: &LinkPreviewSettings) -> Self {
+        Self {
+            timeout: settings.timeout(),
+            max_bytes: settings.max_bytes,
+            max_redirects: settings.max_redirects,
+            allow_private_networks: settings.allow_private_networks,
+        }
+    }
+
+    // the tests serve their pages from 127.0.0.1
+    pub fn with_private_networks(mut self, allow: bool) -> Self {
+        self.allow_private_networks = allow;
+        self
+    }
+
+    pub async fn unfurl(&self, url: &str) -> UnfurlResult<LinkMetadata> {
+        let url = Url::parse(url).map_err(|_| UnfurlError::UnsupportedUrl(url.into()))?;
+
+        tokio::time::timeout(self.timeout, self.follow(url))
+            .await
+            .map_err(|_| UnfurlError::Timeout)?
+    }
+
+    async fn follow(&self, mut url: Url) -> UnfurlResult<LinkMetadata> {
+        for _ in 0..=self.max_redirects {
+            let client = self.client_for(&url).await?;
+            let mut response = client
+                .get(url.clone())
+                .header(ACCEPT, "text/html,application/xhtml+xml")
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .ok_or(UnfurlError::UnexpectedStatus(status))?;
+                url = url
+                    .join(location)
+                    .map_err(|_| UnfurlError::UnsupportedUrl(location.into()))?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(UnfurlError::UnexpectedStatus(status));
+            }
+
+            let content_type = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            if !content_type.starts_with("text/html")
+                && !content_type.starts_with("application/xhtml+xml")
+            {
+                return Err(UnfurlError::NotHtml(content_type));
+            }
+
+            // the rest of a large page is never downloaded
+            let mut html = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                html.extend_from_slice(&chunk);
+                if html.len() >= self.max_bytes {
+                    html.truncate(self.max_bytes);
+                    break;
+                }
+            }
+
+            return Ok(opengraph::parse(&String::from_utf8_lossy(&html), &url));
+        }
+
+        Err(UnfurlError::TooManyRedirects(self.max_redirects))
+    }
+
+    // redirects are followed by hand, so every hop goes through this check
+    async fn client_for(&self, url: &Url) -> UnfurlResult<Client> {
+        let host = match (url.scheme(), url.host_str()) {
+            ("http" | "https", Some(host)) => host,
+            _ => return Err(UnfurlError::UnsupportedUrl(url.to_string())),
+        };
+        let port = url.port_or_known_default().unwrap_or_default();
+
+        // IPv6 literals come with brackets
+        let lookup = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs = tokio::net::lookup_host((lookup, port))
+            .await?
+            .collect::<Vec<SocketAddr>>();
+        if addrs.is_empty() {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, host).into());
+        }
+        let blocked = addrs.iter().find(|addr| !is_public(addr.ip()));
+        if let (false, Some(addr)) = (self.allow_private_networks, blocked) {
+            return Err(UnfurlError::BlockedAddress(addr.ip()));
+        }
+
+        let client = Client::builder()
+            .user_agent(USER_AGENT)
+            .redirect(Policy::none())
+            // a proxy would resolve the host itself and skip the check above
+            .no_proxy()
+            .resolve_to_addrs(host, &addrs)
+            .build()?;
+
+        Ok(client)
+    }
+}
+
+impl Default for Unfurler {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            allow_private_networks: false,
+        }
+    }
+}
+
+// anything but the public internet: loopback, private, link-local, shared (CGNAT),
+// documentation, benchmarking, multicast and reserved ranges
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // unique local fc00::/7 and link-local fe80::/10
+        || (segments[0] & 0xfe00) == 0xfc00
+        || (segments[0] & 0xffc0) == 0xfe80
+        // documentation 2001:db8::/32
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+        // IPv4-compatible ::/96, NAT64 64:ff9b::/96, Teredo 2001::/32 and 6to4
+        // 2002::/16 embed an IPv4 address
+        || segments[..6].iter().all(|&segment| segment == 0)
+        || (segments[0] == 0x0064 && segments[1] == 0xff9b)
+        || (segments[0] == 0x2001 && segments[1] == 0)
+        || segments[0] == 0x2002)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use actix_web::{App, HttpResponse, HttpServer, http::header, web};
+    use expect_test::expect;
+
+    use super::*;
+
+    #[test]
+    fn test_is_public() {
+        let blocked = [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "::127.0.0.1",
+            "::a9fe:a9fe",
+            "2001:0:4136:e378:8000:63bf:3fff:fdd2",
+        ];
+        for ip in blocked {
+            assert!(!is_public(ip.parse().unwrap()), "{ip} should be blocked");
+        }
+        for ip in ["93.184.215.14", "2606:2800:21f:cb07:6820:80da:af6b:8b2c"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip} should be allowed");
+        }
+    }
+
+    async fn page(path: web::Path<String>) -> HttpResponse {
+        match path.as_str() {
+            "article" => HttpResponse::Ok()
+                .content_type("text/html; charset=utf-8")
+                .body(
+                    r#"<html><head>
+                    <meta property="og:title" content="Rust &amp; friends">
+                    <meta property="og:image" content="/cover.png">
+                    <title>ignored</title>
+                </head></html>"#,
+                ),
+            "moved" => HttpResponse::Found()
+                .insert_header((header::LOCATION, "/article"))
+                .finish(),
+            "loop" => HttpResponse::Found()
+                .insert_header((header::LOCATION, "/loop"))
+                .finish(),
+            "slow" => {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                HttpResponse::Ok().finish()
+            }
+            _ => HttpResponse::Ok().content_type("image/png").finish(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_unfurl() {
+        let lst = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = lst.local_addr().unwrap();
+        let server = HttpServer::new(|| App::new().route("/{path}", web::get().to(page)))
+            .workers(1)
+            .listen(lst)
+            .unwrap()
+            .run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let unfurler = Unfurler {
+            timeout: Duration::from_millis(500),
+            allow_private_networks: true,
+            ..Default::default()
+        };
+        let base = format!("http://{addr}");
+
+        let metadata = unfurler.unfurl(&format!("{base}/moved")).await.unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Rust & friends"));
+        assert_eq!(metadata.image_url, Some(format!("{base}/cover.png")));
+
+        let error = |path: &'static str| {
+            let unfurler = unfurler.clone();
+            let url = format!("{base}/{path}");
+            async move { unfurler.unfurl(&url).await.err().unwrap().to_string() }
+        };
+        expect!["More than 3 redirects"].assert_eq(&error("loop").await);
+        expect!["Timed out"].assert_eq(&error("slow").await);
+        expect!["Not an HTML page: image/png"].assert_eq(&error("cover.png").await);
+
+        // the default refuses to connect to the stand-in at all
+        let error = Unfurler::default()
+            .unfurl(&format!("{base}/article"))
+            .await
+            .err()
+            .unwrap();
+        expect!["Address 127.0.0.1 is not publicly routable"].assert_eq(&error.to_string());
+        let error = Unfurler::default()
+            .unfurl("file:///etc/passwd")
+            .await
+            .err()
+            .unwrap();
+        expect!["Only http and https links can be previewed: file:///etc/passwd"]
+            .assert_eq(&error.to_string());
+
+        handle.stop(false).await;
+    }
+}
@@ -0,0 +1,144 @@
+use reqwest::Url;
+use tl::ParserOptions;
+
+const MAX_TITLE_LENGTH: usize = 300;
+const MAX_DESCRIPTION_LENGTH: usize = 1000;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    // absolute, http or https only
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+impl LinkMetadata {
+    // a page without a title or description has nothing worth previewing
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none()
+    }
+}
+
+// OpenGraph tags first, then Twitter cards, the description meta tag and `<title>`
+pub fn parse(html: &str, url: &Url) -> LinkMetadata {
+    let Ok(dom) = tl::parse(html, ParserOptions::default()) else {
+        return LinkMetadata::default();
+    };
+    let parser = dom.parser();
+
+    let mut meta = Vec::new();
+    for tag in dom
+        .query_selector("meta")
+        .into_iter()
+        .flatten()
+        .filter_map(|node| node.get(parser)?.as_tag())
+    {
+        let attributes = tag.attributes();
+        let name = attributes
+            .get("property")
+            .or_else(|| attributes.get("name"))
+            .flatten();
+        let content = attributes.get("content").flatten();
+        if let (Some(name), Some(content)) = (name, content) {
+            meta.push((
+                name.as_utf8_str().to_ascii_lowercase(),
+                content.as_utf8_str().into_owned(),
+            ));
+        }
+    }
+    let find = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            meta.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, content)| content.as_str())
+        })
+    };
+
+    let title = find(&["og:title", "twitter:title"])
+        .map(str::to_owned)
+        .or_else(|| {
+            dom.query_selector("title")?
+                .next()?
+                .get(parser)
+                .map(|node| node.inner_text(parser).into_owned())
+        });
+    let image_url = find(&[
+        "og:image",
+        "og:image:url",
+        "og:image:secure_url",
+        "twitter:image",
+    ])
+    .and_then(|image| url.join(image.trim()).ok())
+    .filter(|image| matches!(image.scheme(), "http" | "https"))
+    .map(String::from);
+
+    LinkMetadata {
+        title: clean(title.as_deref(), MAX_TITLE_LENGTH),
+        description: clean(
+            find(&["og:description", "twitter:description", "description"]),
+            MAX_DESCRIPTION_LENGTH,
+        ),
+        image_url,
+        site_name: clean(find(&["og:site_name"]), MAX_TITLE_LENGTH),
+    }
+}
+
+// decodes entities, collapses whitespace and caps the length
+fn clean(value: Option<&str>, max_length: usize) -> Option<String> {
+    let value = html_escape::decode_html_entities(value?);
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if value.is_empty() {
+        return None;
+    }
+
+    match value.chars().count() > max_length {
+        true => Some(value.chars().take(max_length - 1).collect::<String>() + "…"),
+        false => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let url = Url::parse("https://example.com/posts/1").unwrap();
+
+        let html = r#"<!doctype html><html><head>
+            <title>Fallback</title>
+            <meta name="description" content="  A   plain
+                description ">
+            <meta name="twitter:image" content="javascript:alert(1)">
+            <meta property="og:site_name" content="Example">
+        </head><body></body></html>"#;
+        let metadata = parse(html, &url);
+        assert_eq!(
+            metadata,
+            LinkMetadata {
+                title: Some("Fallback".into()),
+                description: Some("A plain description".into()),
+                image_url: None,
+                site_name: Some("Example".into()),
+            }
+        );
+
+        let html = format!(
+            r#"<meta property="og:title" content="Tom &quot;&amp;&quot; Jerry">
+            <meta property="OG:IMAGE" content="../img/cover.jpg">
+            <meta property="og:description" content="{}">"#,
+            "x".repeat(2000)
+        );
+        let metadata = parse(&html, &url);
+        assert_eq!(metadata.title.as_deref(), Some(r#"Tom "&" Jerry"#));
+        assert_eq!(
+            metadata.image_url.as_deref(),
+            Some("https://example.com/img/cover.jpg")
+        );
+        assert_eq!(metadata.description.unwrap().chars().count(), 1000);
+
+        assert!(parse("<p>just text</p>", &url).is_empty());
+    }
+}
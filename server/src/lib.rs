@@ -1,25 +1,59 @@
 pub mod apis;
 pub mod app;
+pub mod cli;
 pub mod core;
 
-use core::{app_config::AppConfig, app_data::AppData, app_error::AppResult};
+use clap::Parser;
+use core::{
+    app_config::{AppConfig, ConfigOverrides},
+    app_data::AppData,
+    app_error::AppResult,
+    jobs::Jobs,
+    metrics::Metrics,
+    unfurl::Unfurler,
+};
 use std::net::TcpListener;
 
 pub async fn start() -> AppResult<()> {
     dotenvy::dotenv().ok();
-    core::telemetry::init_logger("info");
 
-    let config = AppConfig::configure()?;
-    let jwt_secret = std::env::var("JWT_SECRET")?;
+    cli::Cli::parse().run().await
+}
+
+pub async fn serve(overrides: ConfigOverrides) -> AppResult<()> {
+    let config = AppConfig::configure(overrides)?;
+
+    let pool = core::database::connect(&config.database).await?;
+    if config.database.migrate {
+        core::database::migrate(&pool).await?;
+    }
 
-    let pool = core::database::connect(config.database.options()).await?;
     let lst = TcpListener::bind(config.app.addr())?;
     let app_data = AppData::builder()
         .with_pool(pool)
-        .with_jwt_secret(jwt_secret)
+        .with_jwt_secret(config.jwt.secret.clone())
+        .with_jwt_ttl(chrono::Duration::hours(config.jwt.expiration_hours))
+        .with_metrics(Metrics::new()?)
+        .with_storage(core::storage::from_settings(&config.attachments)?)
+        .with_upload_limits(config.attachments.upload_limits())
+        .with_unfurler(Unfurler::new(&config.link_previews))
         .build()?;
 
-    core::server::run(lst, app_data).await?;
+    let jobs = Jobs::default();
+    core::jobs::spawn_message_purge(&jobs, app_data.clone(), config.messages.clone());
+    core::jobs::spawn_image_worker(&jobs, app_data.clone());
+    core::jobs::sync_disabled_users(&app_data).await?;
+    core::jobs::spawn_user_disabled_listener(&jobs, app_data.clone());
+    if config.link_previews.enabled {
+        core::jobs::spawn_link_preview_worker(&jobs, app_data.clone());
+    }
+    let served = core::server::run(lst, app_data.clone(), &config).await;
+
+    // jobs may be in the middle of a query
+    tracing::info!("stopping background jobs");
+    jobs.shutdown().await;
+    tracing::info!("closing database pool");
+    app_data.pool.close().await;
 
-    Ok(())
+    served
 }